@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A cached AI response. The file name is the hash of provider, model and prompt.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CachedResponse {
+    pub provider: String,
+    pub model: String,
+    /// Unix timestamp of the response.
+    pub created_at: i64,
+    pub text: String,
+}
+
+impl CachedResponse {
+    /// Younger than `ttl_hours`; nothing is fresh with a TTL of 0.
+    fn is_fresh(&self, ttl_hours: u64) -> bool {
+        let oldest = chrono::Utc::now().timestamp() - (ttl_hours * 3600) as i64;
+        ttl_hours > 0 && self.created_at >= oldest
+    }
+}
+
+pub fn cache_key(provider: &str, model: &str, prompt: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [provider, model, prompt] {
+        hasher.update(part.as_bytes());
+        // Separator, so "a" + "bc" and "ab" + "c" differ
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn path(key: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("whid").join("responses").join(format!("{}.json", key)))
+}
+
+/// The cached text for `key` if it is younger than `ttl_hours`; a TTL of 0 disables the cache.
+pub fn lookup(key: &str, ttl_hours: u64) -> Option<String> {
+    if ttl_hours == 0 {
+        return None;
+    }
+    let content = fs::read_to_string(path(key)?).ok()?;
+    let cached: CachedResponse = serde_json::from_str(&content).ok()?;
+    cached.is_fresh(ttl_hours).then_some(cached.text)
+}
+
+pub fn store(key: &str, provider: &str, model: &str, text: &str) -> Result<()> {
+    let path = path(key).ok_or_else(|| anyhow!("No cache directory"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cached = CachedResponse {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+        text: text.to_string(),
+    };
+    fs::write(path, serde_json::to_string(&cached)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(hours_ago: i64) -> CachedResponse {
+        CachedResponse {
+            provider: "gemini".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            created_at: chrono::Utc::now().timestamp() - hours_ago * 3600,
+            text: "- Fixed login".to_string(),
+        }
+    }
+
+    #[test]
+    fn responses_expire_after_the_ttl() {
+        assert!(response(0).is_fresh(24));
+        assert!(response(23).is_fresh(24));
+        assert!(!response(25).is_fresh(24));
+        assert!(!response(0).is_fresh(0));
+    }
+
+    #[test]
+    fn keys_cover_provider_model_and_prompt() {
+        let key = cache_key("gemini", "gemini-2.5-flash", "prompt");
+        assert_eq!(key, cache_key("gemini", "gemini-2.5-flash", "prompt"));
+        assert_eq!(key.len(), 64);
+        assert_ne!(key, cache_key("offline", "gemini-2.5-flash", "prompt"));
+        assert_ne!(key, cache_key("gemini", "gemini-2.5-pro", "prompt"));
+        assert_ne!(key, cache_key("gemini", "gemini-2.5-flash", "other prompt"));
+        // Parts are separated, so moving text between them changes the key
+        assert_ne!(cache_key("a", "bc", "p"), cache_key("ab", "c", "p"));
+    }
+}
@@ -1,17 +1,169 @@
 use config::{Config, ConfigError, File};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
-use toml;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[allow(unused)]
 pub struct Settings {
+    /// "gemini", or "offline" for summaries built locally from the commits.
+    pub provider: String,
     pub gemini_model: String,
     pub gemini_api_key: Option<String>,
     pub prompt_for_api_key: bool,
     pub custom_prompt_path: Option<String>,
+    /// Preset used when none is picked; empty means the custom prompt or the standup prompt.
+    pub default_preset: String,
+    /// Repo directory name to preset, for repos that need a different output.
+    #[serde(default)]
+    pub repo_presets: BTreeMap<String, String>,
     pub lang: Option<String>,
+    pub timezone: String,
+    #[serde(default)]
+    pub intervals: Vec<IntervalConfig>,
+    #[serde(default)]
+    pub team: Vec<TeamMember>,
+    pub team_report: String,
+    pub export_path: String,
+    pub embed_commit_links: bool,
+    /// Show the rendered prompt for review before a summary is requested.
+    pub preview_prompt: bool,
+    /// Ask for JSON sections instead of free text, shown foldable in the popup.
+    pub structured_summary: bool,
+    /// How long AI responses are reused for an identical prompt; 0 disables the cache.
+    pub response_cache_hours: u64,
+    /// Self-hosted hosts and their web UI, for commit links.
+    #[serde(default)]
+    pub commit_links: BTreeMap<String, String>,
+    #[serde(default)]
+    pub publish: Vec<PublishTarget>,
+    pub tracker: Option<TrackerConfig>,
+    pub forge: Option<ForgeConfig>,
+    pub journal: Option<JournalConfig>,
+    pub redaction: Option<RedactionConfig>,
+}
+
+/// The `[redaction]` table: what is masked before commits are sent to the AI.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RedactionConfig {
+    /// The built-in detectors for emails, API keys and IP addresses.
+    #[serde(default = "default_redaction_builtin")]
+    pub builtin: bool,
+    #[serde(default)]
+    pub rules: Vec<RedactionRule>,
+    /// Repo directory name to terms that are always masked, e.g. customer names; "*" applies to every repo.
+    #[serde(default)]
+    pub never_send: BTreeMap<String, Vec<String>>,
+}
+
+fn default_redaction_builtin() -> bool {
+    true
+}
+
+/// One `[[redaction.rules]]` entry: a regex and what it is replaced with.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RedactionRule {
+    /// Shown in the preview's list of masked data; the pattern by default.
+    pub name: Option<String>,
+    pub pattern: String,
+    /// May refer to capture groups as `$1` or `${name}`.
+    #[serde(default = "default_redaction_replacement")]
+    pub replacement: String,
+}
+
+fn default_redaction_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+/// The `[journal]` table: a daily note the summary is written into.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct JournalConfig {
+    /// Note path; `{YYYY-MM-DD}`, `{YYYY}`, `{MM}` and `{DD}` are the last day of the range.
+    pub path: String,
+    /// The section whid owns; it is replaced on every run.
+    #[serde(default = "default_journal_heading")]
+    pub heading: String,
+}
+
+fn default_journal_heading() -> String {
+    "## Standup".to_string()
+}
+
+/// The `[forge]` table: GitHub/GitLab access for pull request activity.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ForgeConfig {
+    /// Forge username; looked up from the token when not set.
+    pub user: Option<String>,
+    /// Tokens; `$VAR` is read from the environment.
+    pub github_token: Option<String>,
+    #[serde(default = "default_github_api")]
+    pub github_api: String,
+    pub gitlab_token: Option<String>,
+    /// GitLab API base; defaults to `https://<host>/api/v4` of each repo.
+    pub gitlab_api: Option<String>,
+    /// Self-hosted hosts and their forge, e.g. `"git.example.com" = "gitlab"`.
+    #[serde(default)]
+    pub hosts: BTreeMap<String, String>,
+}
+
+fn default_github_api() -> String {
+    "https://api.github.com".to_string()
+}
+
+/// The `[tracker]` table: where ticket titles and statuses are looked up.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TrackerConfig {
+    /// Only "jira" for now.
+    pub kind: String,
+    pub url: String,
+    pub user: Option<String>,
+    /// API token; `$VAR` is read from the environment.
+    pub token: String,
+    /// Ticket prefixes to look up, e.g. ["CPT", "DSG"]; empty means all.
+    #[serde(default)]
+    pub projects: Vec<String>,
+    #[serde(default = "default_cache_hours")]
+    pub cache_hours: u64,
+}
+
+fn default_cache_hours() -> u64 {
+    24
+}
+
+/// One `[[publish]]` entry: a named incoming webhook or HTTP endpoint.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PublishTarget {
+    pub name: String,
+    /// slack, mattermost, discord or http
+    pub kind: String,
+    /// Webhook URL; `$VAR` / `${VAR}` are read from the environment.
+    pub url: String,
+    /// HTTP method for `http` targets, POST by default.
+    pub method: Option<String>,
+    /// Extra headers for `http` targets; values support `$VAR` like the URL.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Body template for `http` targets, the JSON document by default.
+    pub body: Option<String>,
+}
+
+/// One `[[team]]` entry: a person and all email addresses they commit with.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TeamMember {
+    pub name: String,
+    pub emails: Vec<String>,
+}
+
+/// One `[[intervals]]` entry: a label plus `hours`, `days`, `calendar` or `from`/`to`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct IntervalConfig {
+    pub label: String,
+    pub hours: Option<u64>,
+    pub days: Option<u64>,
+    pub calendar: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
 }
 
 impl Settings {
@@ -19,10 +171,8 @@ impl Settings {
         let user_config_path = get_user_config_path();
 
         // Ensure the user config directory exists
-        if let Some(parent) = user_config_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).expect("Could not create config directory");
-            }
+        if let Some(parent) = user_config_path.parent() && !parent.exists() {
+            fs::create_dir_all(parent).expect("Could not create config directory");
         }
 
         // Read the blueprint
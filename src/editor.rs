@@ -0,0 +1,167 @@
+use std::fs;
+use std::process::Command;
+use anyhow::{anyhow, Result};
+
+/// A small multi-line text editor for the summary popup. The cursor column
+/// counts characters, not bytes.
+#[derive(Clone, Debug)]
+pub struct TextEditor {
+    pub lines: Vec<String>,
+    pub row: usize,
+    pub col: usize,
+    /// First visible line and column, kept in view of the cursor by the renderer.
+    pub scroll: usize,
+    pub hscroll: usize,
+}
+
+impl TextEditor {
+    /// Starts editing `text` with the cursor at its end.
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+        TextEditor { lines, row, col, scroll: 0, hscroll: 0 }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn byte_index(&self, col: usize) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(col).map(|(i, _)| i).unwrap_or(line.len())
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let at = self.byte_index(self.col);
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+    }
+
+    pub fn newline(&mut self) {
+        let at = self.byte_index(self.col);
+        let rest = self.lines[self.row].split_off(at);
+        self.lines.insert(self.row + 1, rest);
+        self.row += 1;
+        self.col = 0;
+    }
+
+    /// Deletes the character before the cursor, joining lines at the start of one.
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let at = self.byte_index(self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Deletes the character under the cursor, joining with the next line at the end.
+    pub fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let at = self.byte_index(self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    /// Removes the cursor's line, keeping at least one empty line.
+    pub fn delete_line(&mut self) {
+        self.lines.remove(self.row);
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.row = self.row.min(self.lines.len() - 1);
+        self.col = self.col.min(self.line_len());
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.line_len();
+    }
+
+    /// Adjusts the scroll offsets so the cursor is inside a `width` x `height` view.
+    pub fn scroll_to_cursor(&mut self, width: usize, height: usize) {
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if height > 0 && self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+        if self.col < self.hscroll {
+            self.hscroll = self.col;
+        } else if width > 0 && self.col >= self.hscroll + width {
+            self.hscroll = self.col + 1 - width;
+        }
+    }
+}
+
+/// Opens `text` in `$VISUAL` or `$EDITOR` (falling back to `vi`) and returns
+/// the saved result. The caller has to release the terminal first.
+pub fn edit_externally(text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let path = std::env::temp_dir().join(format!("whid-summary-{}.md", std::process::id()));
+    fs::write(&path, format!("{}\n", text))?;
+    // The variable may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(&path).status();
+    let result = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path).map_err(Into::into),
+        Ok(status) => Err(anyhow!("{} exited with {}", editor, status)),
+        Err(e) => Err(anyhow!("Could not start {}: {}", editor, e)),
+    };
+    let _ = fs::remove_file(&path);
+    result.map(|edited| edited.trim_end_matches('\n').to_string())
+}
@@ -0,0 +1,420 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use crate::range::TimeRange;
+use crate::remote::CommitLinker;
+use crate::structured::StructuredSummary;
+use crate::utils::{extract_tickets, CommitData};
+
+/// File formats supported by the `e` key and `whid export`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Csv,
+    Html,
+    Text,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            "txt" | "text" => Ok(ExportFormat::Text),
+            other => Err(anyhow!("Unknown export format '{}'. Use md, json, csv, html or txt.", other)),
+        }
+    }
+
+    /// Guesses the format from the file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|ext| Self::parse(&ext.to_string_lossy()).ok())
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Html => "html",
+            ExportFormat::Text => "txt",
+        }
+    }
+}
+
+/// One commit as shown in the commit list.
+#[derive(Debug, Serialize)]
+pub struct ExportCommit {
+    pub repo: String,
+    pub hash: String,
+    pub date: String,
+    pub author: Option<String>,
+    pub subject: String,
+    pub tickets: Vec<String>,
+    /// Web URL of the commit, when links are enabled and the forge is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl ExportCommit {
+    /// Parses a commit list line (`hash|date|[author|]subject`) or a detailed
+    /// multi-line block (`hash date time`, message, `(author)`).
+    pub fn parse(repo: &str, line: &str, filter_by_user: bool) -> Self {
+        if line.contains('\n') {
+            let mut lines = line.lines();
+            let header = lines.next().unwrap_or_default();
+            let (hash, date) = header.split_once(' ').unwrap_or((header, ""));
+            let mut message: Vec<&str> = lines.collect();
+            let mut author = None;
+            if let Some(last) = message.last().map(|l| l.trim()) && let Some(name) = last.strip_prefix('(').and_then(|l| l.strip_suffix(')')) {
+                author = Some(name.to_string());
+                message.pop();
+            }
+            let subject = message.join("\n").trim().to_string();
+            return ExportCommit {
+                repo: repo.to_string(),
+                hash: hash.trim().to_string(),
+                date: date.trim().to_string(),
+                author,
+                tickets: extract_tickets(&subject),
+                subject,
+                url: None,
+            };
+        }
+        let parts: Vec<&str> = if filter_by_user { line.splitn(3, '|').collect() } else { line.splitn(4, '|').collect() };
+        let field = |i: usize| parts.get(i).map(|s| s.trim().to_string()).unwrap_or_default();
+        let (author, subject) = if filter_by_user { (None, field(2)) } else { (Some(field(2)), field(3)) };
+        ExportCommit {
+            repo: repo.to_string(),
+            hash: field(0),
+            date: field(1),
+            author,
+            tickets: extract_tickets(&subject),
+            subject,
+            url: None,
+        }
+    }
+}
+
+/// The summary and commit list to export, taken from what the TUI shows.
+#[derive(Debug, Serialize)]
+pub struct ExportDoc {
+    pub from: String,
+    pub to: String,
+    pub interval: String,
+    pub summary: Option<String>,
+    /// Set when the summary came back structured; `summary` is its Markdown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sections: Option<StructuredSummary>,
+    pub commits: Vec<ExportCommit>,
+}
+
+impl ExportDoc {
+    /// `links` adds commit URLs; pass `None` when `embed_commit_links` is off.
+    pub fn new(range: &TimeRange, summary: Option<String>, commits: &CommitData, filter_by_user: bool, links: Option<&CommitLinker>) -> Self {
+        let commits = commits
+            .iter()
+            .flat_map(|(repo, lines)| {
+                let repo_name = repo.file_name().unwrap_or_default().to_string_lossy().to_string();
+                lines.iter().map(move |line| {
+                    let mut commit = ExportCommit::parse(&repo_name, line, filter_by_user);
+                    commit.url = links.and_then(|l| l.commit_url(repo, &commit.hash));
+                    commit
+                })
+            })
+            .collect();
+        ExportDoc {
+            from: range.start_date(),
+            to: range.end_date(),
+            interval: range.label.clone(),
+            summary,
+            sections: None,
+            commits,
+        }
+    }
+
+    pub fn render(&self, format: ExportFormat) -> Result<String> {
+        Ok(match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Json => serde_json::to_string_pretty(self)?,
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Html => self.to_html(),
+            ExportFormat::Text => self.to_text(),
+        })
+    }
+
+    fn title(&self) -> String {
+        if self.from == self.to {
+            format!("Standup {}", self.to)
+        } else {
+            format!("Standup {} – {}", self.from, self.to)
+        }
+    }
+
+    /// Commits grouped by repo, keeping the list order.
+    fn by_repo(&self) -> Vec<(&str, Vec<&ExportCommit>)> {
+        let mut groups: Vec<(&str, Vec<&ExportCommit>)> = vec![];
+        for commit in &self.commits {
+            match groups.last_mut() {
+                Some((repo, list)) if *repo == commit.repo => list.push(commit),
+                _ => groups.push((&commit.repo, vec![commit])),
+            }
+        }
+        groups
+    }
+
+    fn commit_line(commit: &ExportCommit) -> String {
+        let subject = commit.subject.lines().next().unwrap_or_default();
+        match &commit.author {
+            Some(author) if !author.is_empty() => format!("{} {} {} ({})", commit.hash, commit.date, subject, author),
+            _ => format!("{} {} {}", commit.hash, commit.date, subject),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n_Interval: {}_\n", self.title(), self.interval);
+        if let Some(summary) = &self.summary {
+            out.push_str(&format!("\n## Summary\n\n{}\n", summary.trim()));
+        }
+        if !self.commits.is_empty() {
+            out.push_str("\n## Commits\n");
+            out.push_str(&self.commits_markdown());
+        }
+        out
+    }
+
+    /// The commit list as Markdown, one `###` section per repo.
+    pub fn commits_markdown(&self) -> String {
+        let mut out = String::new();
+        for (repo, commits) in self.by_repo() {
+            out.push_str(&format!("\n### {}\n\n", repo));
+            for commit in commits {
+                let subject = commit.subject.lines().next().unwrap_or_default();
+                match &commit.url {
+                    Some(url) => out.push_str(&format!("- [`{}`]({}) {} {}", commit.hash, url, commit.date, subject)),
+                    None => out.push_str(&format!("- `{}` {} {}", commit.hash, commit.date, subject)),
+                }
+                if let Some(author) = commit.author.as_deref().filter(|a| !a.is_empty()) {
+                    out.push_str(&format!(" _({})_", author));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = format!("{}\nInterval: {}\n", self.title(), self.interval);
+        if let Some(summary) = &self.summary {
+            out.push_str(&format!("\n{}\n", summary.trim()));
+        }
+        for (repo, commits) in self.by_repo() {
+            out.push_str(&format!("\n{}\n", repo));
+            for commit in commits {
+                out.push_str(&format!("  {}\n", Self::commit_line(commit)));
+            }
+        }
+        out
+    }
+
+    /// One row per commit; the summary does not fit a table and is left out.
+    fn to_csv(&self) -> String {
+        let mut out = String::from("repo,hash,date,author,subject,tickets,url\n");
+        for c in &self.commits {
+            let row = [
+                c.repo.as_str(),
+                c.hash.as_str(),
+                c.date.as_str(),
+                c.author.as_deref().unwrap_or_default(),
+                c.subject.as_str(),
+                &c.tickets.join(" "),
+                c.url.as_deref().unwrap_or_default(),
+            ]
+            .map(csv_field)
+            .join(",");
+            out.push_str(&row);
+            out.push('\n');
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let title = html_escape(&self.title());
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n<p><em>Interval: {}</em></p>\n",
+            html_escape(&self.interval)
+        );
+        match (&self.sections, &self.summary) {
+            (Some(sections), _) => {
+                out.push_str("<h2>Summary</h2>\n");
+                for project in &sections.projects {
+                    out.push_str(&format!("<h3>{}</h3>\n", html_escape(project.name.trim())));
+                    for topic in &project.topics {
+                        out.push_str(&format!("<h4>{}</h4>\n<ul>\n", html_escape(topic.title.trim())));
+                        for bullet in &topic.bullets {
+                            out.push_str(&format!("<li>{}</li>\n", html_escape(bullet.trim())));
+                        }
+                        out.push_str("</ul>\n");
+                    }
+                    if !project.days.is_empty() {
+                        out.push_str("<h4>Daily breakdown</h4>\n<ul>\n");
+                        for day in &project.days {
+                            out.push_str(&format!("<li><strong>{}</strong>: {}</li>\n", html_escape(day.date.trim()), html_escape(day.text.trim())));
+                        }
+                        out.push_str("</ul>\n");
+                    }
+                }
+            }
+            (None, Some(summary)) => out.push_str(&format!("<h2>Summary</h2>\n<pre>{}</pre>\n", html_escape(summary.trim()))),
+            (None, None) => {}
+        }
+        if !self.commits.is_empty() {
+            out.push_str("<h2>Commits</h2>\n");
+            for (repo, commits) in self.by_repo() {
+                out.push_str(&format!("<h3>{}</h3>\n<ul>\n", html_escape(repo)));
+                for commit in commits {
+                    let line = html_escape(&Self::commit_line(commit));
+                    match &commit.url {
+                        Some(url) => {
+                            let hash = html_escape(&commit.hash);
+                            let linked = line.replacen(&hash, &format!("<a href=\"{}\">{}</a>", html_escape(url), hash), 1);
+                            out.push_str(&format!("<li>{}</li>\n", linked));
+                        }
+                        None => out.push_str(&format!("<li>{}</li>\n", line)),
+                    }
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Expands `~` and the `{date}`, `{from}`, `{to}` and `{interval}` placeholders.
+/// `{date}` is the last day of the range.
+pub fn resolve_export_path(pattern: &str, range: &TimeRange) -> PathBuf {
+    let interval: String = range.label
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let path = pattern
+        .replace("{date}", &range.end_date())
+        .replace("{from}", &range.start_date())
+        .replace("{to}", &range.end_date())
+        .replace("{interval}", &interval);
+    PathBuf::from(shellexpand::tilde(&path).into_owned())
+}
+
+/// The file `write_export` writes for `pattern`, and its format. The format
+/// defaults to the file extension, then Markdown; a directory gets a
+/// `whid-{date}` file inside it.
+pub fn export_target(pattern: &str, format: Option<ExportFormat>, range: &TimeRange) -> (PathBuf, ExportFormat) {
+    let mut path = resolve_export_path(pattern, range);
+    let format = format.or_else(|| ExportFormat::from_path(&path)).unwrap_or(ExportFormat::Markdown);
+    if path.is_dir() {
+        path = path.join(format!("whid-{}.{}", range.end_date(), format.extension()));
+    }
+    (path, format)
+}
+
+/// Writes `doc` to the resolved `pattern`. An existing file is only replaced
+/// with `overwrite`.
+pub fn write_export(pattern: &str, format: Option<ExportFormat>, doc: &ExportDoc, range: &TimeRange, overwrite: bool) -> Result<PathBuf> {
+    let (path, format) = export_target(pattern, format, range);
+    if !overwrite && path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, doc.render(format)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::{DateBounds, Interval, Zone};
+
+    fn range() -> TimeRange {
+        let bounds = DateBounds::parse(Some("2024-05-01"), Some("2024-05-02")).unwrap();
+        TimeRange::resolve(&bounds, &Interval::rolling("24h", 24), Zone::parse("UTC").unwrap())
+    }
+
+    fn doc(summary: Option<&str>) -> ExportDoc {
+        let commits = vec![
+            (PathBuf::from("/src/web"), vec![
+                "abc1234|2024-05-01 10:00|Alice|Fix \"login\", closes CPT-12".to_string(),
+                "def5678|2024-05-02 09:30|Bob|Add <b>bold</b> & tidy".to_string(),
+            ]),
+            (PathBuf::from("/src/api"), vec!["0123abc|2024-05-02 11:00|Alice|Bump deps".to_string()]),
+        ];
+        ExportDoc::new(&range(), summary.map(str::to_string), &commits, false, None)
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_separators() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        let csv = doc(None).render(ExportFormat::Csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], "repo,hash,date,author,subject,tickets,url");
+        assert_eq!(rows[1], "web,abc1234,2024-05-01 10:00,Alice,\"Fix \"\"login\"\", closes CPT-12\",CPT-12,");
+        assert_eq!(rows.len(), 4);
+    }
+
+    #[test]
+    fn html_escapes_text_and_summary() {
+        assert_eq!(html_escape("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+        let html = doc(Some("Shipped <script>")).render(ExportFormat::Html).unwrap();
+        assert!(html.contains("<pre>Shipped &lt;script&gt;</pre>"), "{}", html);
+        assert!(html.contains("<li>def5678 2024-05-02 09:30 Add &lt;b&gt;bold&lt;/b&gt; &amp; tidy (Bob)</li>"), "{}", html);
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn markdown_has_summary_and_commits_per_repo() {
+        let markdown = doc(Some("- Fixed login\n")).render(ExportFormat::Markdown).unwrap();
+        assert_eq!(
+            markdown,
+            "# Standup 2024-05-01 – 2024-05-02\n\n_Interval: 2024-05-01 to 2024-05-02_\n\n## Summary\n\n- Fixed login\n\n## Commits\n\
+             \n### web\n\n- `abc1234` 2024-05-01 10:00 Fix \"login\", closes CPT-12 _(Alice)_\n- `def5678` 2024-05-02 09:30 Add <b>bold</b> & tidy _(Bob)_\n\
+             \n### api\n\n- `0123abc` 2024-05-02 11:00 Bump deps _(Alice)_\n"
+        );
+    }
+
+    #[test]
+    fn refuses_to_overwrite_without_being_asked() {
+        let dir = std::env::temp_dir().join(format!("whid-export-test-{}", std::process::id()));
+        let pattern = dir.join("standup-{date}.md").to_string_lossy().to_string();
+        let doc = doc(None);
+        let path = write_export(&pattern, None, &doc, &range(), false).unwrap();
+        assert_eq!(path, dir.join("standup-2024-05-02.md"));
+        let err = write_export(&pattern, None, &doc, &range(), false).unwrap_err().to_string();
+        assert!(err.contains("already exists"), "{}", err);
+        assert!(write_export(&pattern, None, &doc, &range(), true).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset};
+use serde_json::Value;
+use crate::config::ForgeConfig;
+use crate::range::TimeRange;
+use crate::remote::{origin, RemoteRepo};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+impl ForgeKind {
+    /// github.com and hosts with "gitlab" in their name are detected; other
+    /// hosts need an entry in `[forge.hosts]`.
+    pub fn detect(host: &str, config: &ForgeConfig) -> Option<Self> {
+        match config.hosts.get(host).map(|k| k.to_lowercase()).as_deref() {
+            Some("github") => return Some(ForgeKind::GitHub),
+            Some("gitlab") => return Some(ForgeKind::GitLab),
+            _ => {}
+        }
+        if host == "github.com" {
+            Some(ForgeKind::GitHub)
+        } else if host.contains("gitlab") {
+            Some(ForgeKind::GitLab)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActivityKind {
+    Opened,
+    Merged,
+    Reviewed,
+    Commented,
+}
+
+impl ActivityKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ActivityKind::Opened => "Opened",
+            ActivityKind::Merged => "Merged",
+            ActivityKind::Reviewed => "Reviewed",
+            ActivityKind::Commented => "Commented on",
+        }
+    }
+}
+
+/// One pull/merge request event by the user.
+#[derive(Clone, Debug)]
+pub struct ForgeItem {
+    pub repo: String,
+    pub kind: ActivityKind,
+    /// `#12` on GitHub, `!12` on GitLab.
+    pub reference: String,
+    pub title: String,
+    pub url: String,
+    pub date: String,
+}
+
+/// PR/MR activity for the current range, filled in the background.
+#[derive(Debug, Default)]
+pub struct ForgeActivity {
+    /// The range the items belong to; a new range triggers a new fetch.
+    pub range_key: String,
+    pub loading: bool,
+    pub items: Vec<ForgeItem>,
+    pub errors: Vec<String>,
+}
+
+impl ForgeActivity {
+    pub fn count(&self, kind: ActivityKind) -> usize {
+        self.items.iter().filter(|i| i.kind == kind).count()
+    }
+
+    /// The activity block for the summary prompt, optionally for one repo only.
+    pub fn for_prompt(&self, repo: Option<&str>) -> String {
+        self.items
+            .iter()
+            .filter(|i| repo.is_none_or(|r| i.repo == r))
+            .map(|i| format!("- [{}] {} {} {} ({}) {}", i.repo, i.kind.label(), i.reference, i.title, i.date, i.url))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub fn range_key(range: &TimeRange) -> String {
+    format!("{}|{}", range.git_since(), range.git_until())
+}
+
+/// Remotes of `repos` on a supported forge, with the local repo name.
+pub fn forge_remotes(repos: &[PathBuf], config: &ForgeConfig) -> Vec<(String, ForgeKind, RemoteRepo)> {
+    repos
+        .iter()
+        .filter_map(|repo| {
+            let remote = origin(repo)?;
+            let kind = ForgeKind::detect(&remote.host, config)?;
+            let name = repo.canonicalize().ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| remote.name().to_string());
+            Some((name, kind, remote))
+        })
+        .collect()
+}
+
+/// Fetches the user's PR/MR activity in `range` for every remote.
+/// Failures are collected per repo so one bad remote does not hide the rest.
+pub async fn fetch_activity(config: &ForgeConfig, remotes: &[(String, ForgeKind, RemoteRepo)], range: &TimeRange) -> (Vec<ForgeItem>, Vec<String>) {
+    let mut client = ForgeClient { config, http: reqwest::Client::new(), users: HashMap::new(), truncated: vec![] };
+    let mut items = vec![];
+    let mut errors = vec![];
+    for (name, kind, remote) in remotes {
+        let result = match kind {
+            ForgeKind::GitHub => client.github(name, remote, range).await,
+            ForgeKind::GitLab => client.gitlab(name, remote, range).await,
+        };
+        match result {
+            Ok(mut found) => items.append(&mut found),
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+        for query in client.truncated.drain(..) {
+            errors.push(format!("{}: results truncated after {} pages ({})", name, MAX_PAGES, query));
+        }
+    }
+    items.sort_by(|a, b| a.date.cmp(&b.date));
+    (items, errors)
+}
+
+struct ForgeClient<'a> {
+    config: &'a ForgeConfig,
+    http: reqwest::Client,
+    /// Usernames looked up per API base.
+    users: HashMap<String, String>,
+    /// Queries with more pages than `MAX_PAGES`, reported with the errors.
+    truncated: Vec<String>,
+}
+
+// 1000 results per query, as far as GitHub's search goes
+const MAX_PAGES: usize = 10;
+
+fn expand(value: &str) -> Result<String> {
+    Ok(shellexpand::env(value).map_err(|e| anyhow!("[forge]: {}", e))?.into_owned())
+}
+
+fn in_range(timestamp: &str, range: &TimeRange) -> bool {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t >= range.since && t <= range.until)
+        .unwrap_or(false)
+}
+
+// UTC with `Z`, so the timestamp needs no escaping in query strings
+fn query_time(time: &DateTime<FixedOffset>) -> String {
+    time.to_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn day(timestamp: &str) -> String {
+    timestamp.chars().take(10).collect()
+}
+
+/// The URL of the next page: the `rel="next"` entry of the `Link` header, or
+/// GitLab's `X-Next-Page` number applied to `url`.
+fn next_page(url: &str, headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(link) = header("link") {
+        let next = link.split(',').find_map(|part| {
+            let (target, params) = part.split_once(';')?;
+            params.split(';').any(|p| p.trim() == "rel=\"next\"").then(|| target.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+        });
+        if next.is_some() {
+            return next;
+        }
+    }
+    let page = header("x-next-page").map(str::trim).filter(|p| !p.is_empty())?;
+    let mut next = reqwest::Url::parse(url).ok()?;
+    let pairs: Vec<(String, String)> = next.query_pairs().filter(|(k, _)| k != "page").map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    next.query_pairs_mut().clear().extend_pairs(pairs).append_pair("page", page);
+    Some(next.to_string())
+}
+
+impl ForgeClient<'_> {
+    async fn get(&self, url: &str, kind: ForgeKind) -> Result<Value> {
+        Ok(self.send(url, kind).await?.json().await?)
+    }
+
+    /// Fetches `url` and the pages after it, up to `MAX_PAGES`. `results` picks
+    /// the entries out of a page: the page itself, or a search's `items`.
+    async fn get_pages(&mut self, url: &str, kind: ForgeKind, results: fn(Value) -> Value) -> Result<Vec<Value>> {
+        let mut all = vec![];
+        let mut next = Some(url.to_string());
+        for _ in 0..MAX_PAGES {
+            let Some(page_url) = next.take() else {
+                return Ok(all);
+            };
+            let response = self.send(&page_url, kind).await?;
+            next = next_page(&page_url, response.headers());
+            if let Value::Array(mut entries) = results(response.json().await?) {
+                all.append(&mut entries);
+            }
+        }
+        if next.is_some() {
+            self.truncated.push(url.split_once('?').map_or(url, |(_, query)| query).to_string());
+        }
+        Ok(all)
+    }
+
+    async fn send(&self, url: &str, kind: ForgeKind) -> Result<reqwest::Response> {
+        let mut request = self.http.get(url).header("User-Agent", "whid");
+        match kind {
+            ForgeKind::GitHub => {
+                request = request.header("Accept", "application/vnd.github+json");
+                if let Some(token) = &self.config.github_token {
+                    request = request.bearer_auth(expand(token)?);
+                }
+            }
+            ForgeKind::GitLab => {
+                if let Some(token) = &self.config.gitlab_token {
+                    request = request.header("PRIVATE-TOKEN", expand(token)?);
+                }
+            }
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("{} returned {}", url, status));
+        }
+        Ok(response)
+    }
+
+    /// The configured user, or the owner of the token.
+    async fn user(&mut self, api: &str, kind: ForgeKind) -> Result<String> {
+        if let Some(user) = self.config.user.as_ref().filter(|u| !u.is_empty()) {
+            return Ok(user.clone());
+        }
+        if let Some(user) = self.users.get(api) {
+            return Ok(user.clone());
+        }
+        let me = self.get(&format!("{}/user", api), kind).await?;
+        let field = if kind == ForgeKind::GitHub { "login" } else { "username" };
+        let user = me[field].as_str().ok_or_else(|| anyhow!("could not determine the forge user; set user in [forge]"))?.to_string();
+        self.users.insert(api.to_string(), user.clone());
+        Ok(user)
+    }
+
+    async fn github(&mut self, name: &str, remote: &RemoteRepo, range: &TimeRange) -> Result<Vec<ForgeItem>> {
+        let api = expand(&self.config.github_api)?.trim_end_matches('/').to_string();
+        let user = self.user(&api, ForgeKind::GitHub).await?;
+        let dates = format!("{}..{}", range.start_date(), range.end_date());
+        let searches = [
+            (ActivityKind::Opened, format!("author:{} created:{}", user, dates), "created_at"),
+            (ActivityKind::Merged, format!("author:{} merged:{}", user, dates), "closed_at"),
+            (ActivityKind::Reviewed, format!("reviewed-by:{} -author:{} updated:{}", user, user, dates), "updated_at"),
+        ];
+        let mut items = vec![];
+        for (kind, query, date_field) in searches {
+            let url = format!("{}/search/issues?per_page=100&q=repo:{}+type:pr+{}", api, remote.path, query.replace(' ', "+"));
+            let found = self.get_pages(&url, ForgeKind::GitHub, |mut page| page["items"].take()).await?;
+            for pr in &found {
+                items.push(ForgeItem {
+                    repo: name.to_string(),
+                    kind,
+                    reference: format!("#{}", pr["number"]),
+                    title: pr["title"].as_str().unwrap_or_default().to_string(),
+                    url: pr["html_url"].as_str().unwrap_or_default().to_string(),
+                    date: day(pr[date_field].as_str().unwrap_or_default()),
+                });
+            }
+        }
+        let url = format!("{}/repos/{}/pulls/comments?per_page=100&since={}", api, remote.path, query_time(&range.since));
+        let comments = self.get_pages(&url, ForgeKind::GitHub, |page| page).await?;
+        for comment in &comments {
+            let created = comment["created_at"].as_str().unwrap_or_default();
+            if comment["user"]["login"].as_str() != Some(user.as_str()) || !in_range(created, range) {
+                continue;
+            }
+            let number = comment["pull_request_url"].as_str().and_then(|u| u.rsplit('/').next()).unwrap_or_default();
+            items.push(ForgeItem {
+                repo: name.to_string(),
+                kind: ActivityKind::Commented,
+                reference: format!("#{}", number),
+                title: comment_excerpt(comment["body"].as_str().unwrap_or_default()),
+                url: comment["html_url"].as_str().unwrap_or_default().to_string(),
+                date: day(created),
+            });
+        }
+        Ok(items)
+    }
+
+    async fn gitlab(&mut self, name: &str, remote: &RemoteRepo, range: &TimeRange) -> Result<Vec<ForgeItem>> {
+        let api = match &self.config.gitlab_api {
+            Some(api) => expand(api)?,
+            None => format!("https://{}/api/v4", remote.host),
+        };
+        let api = api.trim_end_matches('/').to_string();
+        let user = self.user(&api, ForgeKind::GitLab).await?;
+        let project = format!("{}/projects/{}", api, remote.path.replace('/', "%2F"));
+        let since = query_time(&range.since);
+        let until = query_time(&range.until);
+        let searches = [
+            (ActivityKind::Opened, format!("author_username={}&created_after={}&created_before={}", user, since, until), "created_at"),
+            (ActivityKind::Merged, format!("author_username={}&state=merged&updated_after={}", user, since), "merged_at"),
+            (ActivityKind::Reviewed, format!("reviewer_username={}&updated_after={}&updated_before={}", user, since, until), "updated_at"),
+        ];
+        let mut items = vec![];
+        for (kind, query, date_field) in searches {
+            let url = format!("{}/merge_requests?scope=all&per_page=100&{}", project, query);
+            let found = self.get_pages(&url, ForgeKind::GitLab, |page| page).await?;
+            for mr in &found {
+                let date = mr[date_field].as_str().unwrap_or_default();
+                if kind == ActivityKind::Merged && !in_range(date, range) {
+                    continue;
+                }
+                items.push(ForgeItem {
+                    repo: name.to_string(),
+                    kind,
+                    reference: format!("!{}", mr["iid"]),
+                    title: mr["title"].as_str().unwrap_or_default().to_string(),
+                    url: mr["web_url"].as_str().unwrap_or_default().to_string(),
+                    date: day(date),
+                });
+            }
+        }
+        // Events filter by day only, so widen by one day and check the timestamp
+        let after = range.since.date_naive().pred_opt().unwrap_or(range.since.date_naive());
+        let before = range.until.date_naive().succ_opt().unwrap_or(range.until.date_naive());
+        let url = format!("{}/events?action=commented&per_page=100&after={}&before={}", project, after, before);
+        let events = self.get_pages(&url, ForgeKind::GitLab, |page| page).await?;
+        for event in &events {
+            let created = event["created_at"].as_str().unwrap_or_default();
+            if event["author_username"].as_str() != Some(user.as_str())
+                || event["note"]["noteable_type"].as_str() != Some("MergeRequest")
+                || !in_range(created, range)
+            {
+                continue;
+            }
+            let iid = event["note"]["noteable_iid"].as_u64().or(event["target_iid"].as_u64()).unwrap_or_default();
+            items.push(ForgeItem {
+                repo: name.to_string(),
+                kind: ActivityKind::Commented,
+                reference: format!("!{}", iid),
+                title: event["target_title"].as_str().unwrap_or_default().to_string(),
+                url: format!("https://{}/{}/-/merge_requests/{}#note_{}", remote.host, remote.path, iid, event["note"]["id"]),
+                date: day(created),
+            });
+        }
+        Ok(items)
+    }
+}
+
+fn comment_excerpt(body: &str) -> String {
+    let line = body.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+    if line.chars().count() > 60 {
+        format!("\"{}…\"", line.chars().take(60).collect::<String>())
+    } else {
+        format!("\"{}\"", line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs.iter().map(|(name, value)| (reqwest::header::HeaderName::from_static(name), HeaderValue::from_static(value))).collect()
+    }
+
+    #[test]
+    fn follows_the_next_link() {
+        let link = r#"<https://api.github.com/search/issues?q=x&page=2>; rel="next", <https://api.github.com/search/issues?q=x&page=5>; rel="last""#;
+        assert_eq!(
+            next_page("https://api.github.com/search/issues?q=x", &headers(&[("link", link)])).as_deref(),
+            Some("https://api.github.com/search/issues?q=x&page=2")
+        );
+    }
+
+    #[test]
+    fn the_last_page_has_no_next() {
+        let link = r#"<https://api.github.com/x?page=1>; rel="first", <https://api.github.com/x?page=4>; rel="prev""#;
+        assert_eq!(next_page("https://api.github.com/x?page=5", &headers(&[("link", link)])), None);
+        assert_eq!(next_page("https://api.github.com/x", &HeaderMap::new()), None);
+        assert_eq!(next_page("https://gitlab.com/api/v4/x", &headers(&[("x-next-page", "")])), None);
+    }
+
+    #[test]
+    fn applies_gitlab_next_page_numbers() {
+        let url = "https://gitlab.com/api/v4/projects/a%2Fb/merge_requests?per_page=100&updated_after=2024-05-01T00:00:00Z&page=2";
+        assert_eq!(
+            next_page(url, &headers(&[("x-next-page", "3")])).as_deref(),
+            Some("https://gitlab.com/api/v4/projects/a%2Fb/merge_requests?per_page=100&updated_after=2024-05-01T00%3A00%3A00Z&page=3")
+        );
+    }
+}
@@ -1,7 +1,8 @@
-use std::{fs, path::PathBuf, process::Command, time::{Duration, SystemTime}};
-use chrono::{DateTime, Local};
+use std::{fs, path::PathBuf, process::Command};
 use anyhow::Result;
 use std::sync::OnceLock;
+use serde::Serialize;
+use crate::range::TimeRange;
 
 pub fn find_git_repos(start_dir: &str) -> Result<Vec<PathBuf>> {
     let mut repos = vec![];
@@ -43,46 +44,36 @@ pub fn get_current_git_user() -> Result<String> {
 
 pub fn get_recent_commits(
     repo: &PathBuf,
-    interval: Duration,
+    range: &TimeRange,
     filter_by_user: bool,
     detailed: bool,
-    from: Option<String>,
-    to: Option<String>,
+    authors: &[String],
 ) -> Result<Vec<String>> {
     let mut cmd = Command::new("git");
     cmd.arg("-C")
         .arg(repo)
         .arg("log");
 
-    if let Some(from_date) = from {
-        cmd.arg("--since").arg(from_date);
-        if let Some(to_date) = to {
-            cmd.arg("--until").arg(to_date);
-        }
-    } else {
-        let since = SystemTime::now() - interval;
-        let since_datetime: DateTime<Local> = since.into();
-        let since_str = since_datetime.format("%Y-%m-%d %H:%M:%S").to_string();
-        cmd.arg("--since").arg(&since_str);
+    cmd.arg("--since").arg(range.git_since());
+    cmd.arg("--until").arg(range.git_until());
+
+    // Print commit dates in the configured timezone so daily grouping matches the range
+    if let Some(tz) = range.zone.tz_name() {
+        cmd.env("TZ", tz);
     }
+    cmd.arg("--date=format-local:%Y-%m-%d %H:%M");
 
     if detailed {
         // Use a unique separator for robust splitting, and show date+time (hh:mm)
-        cmd.arg("--date=format:%Y-%m-%d %H:%M");
         cmd.arg("--format=%h %ad%n%B (%an)%n---GITBLOCK---");
     } else {
-        cmd.arg("--date=format:%Y-%m-%d %H:%M");
         if filter_by_user {
             cmd.arg("--pretty=format:%h|%ad|%s");
-            static USER_EMAIL: OnceLock<Option<String>> = OnceLock::new();
-            let user = USER_EMAIL.get_or_init(|| get_current_git_user().ok());
-            if let Some(user) = user {
-                cmd.arg("--author").arg(user);
-            }
         } else {
             cmd.arg("--pretty=format:%h|%ad|%an|%s");
         }
     }
+    add_author_filter(&mut cmd, filter_by_user, authors);
 
     let output = cmd.output()?;
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -94,6 +85,118 @@ pub fn get_recent_commits(
     }
 }
 
+/// Restricts `git log` to the current user, or to explicitly picked authors.
+fn add_author_filter(cmd: &mut Command, filter_by_user: bool, authors: &[String]) {
+    if filter_by_user {
+        static USER_EMAIL: OnceLock<Option<String>> = OnceLock::new();
+        let user = USER_EMAIL.get_or_init(|| get_current_git_user().ok());
+        if let Some(user) = user {
+            cmd.arg("--author").arg(user);
+        }
+    } else if !authors.is_empty() {
+        // Explicit author picks (ORed by git); match whole emails literally, so
+        // "al@example.com" does not also pick "val@example.com"
+        cmd.arg("--fixed-strings");
+        for author in authors {
+            cmd.arg(format!("--author=<{}>", author));
+        }
+    }
+}
+
+/// Line counts from `git log --shortstat`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct CommitStats {
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// A fully parsed commit, for machine-readable output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitRecord {
+    pub hash: String,
+    pub short_hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date, ISO 8601 in the range's timezone.
+    pub date: String,
+    pub subject: String,
+    pub body: String,
+    pub stats: CommitStats,
+}
+
+/// Loads full commit records for the same range and author filter as the commit list.
+pub fn get_commit_records(repo: &PathBuf, range: &TimeRange, filter_by_user: bool, authors: &[String]) -> Result<Vec<CommitRecord>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(repo).arg("log");
+    cmd.arg("--since").arg(range.git_since());
+    cmd.arg("--until").arg(range.git_until());
+    if let Some(tz) = range.zone.tz_name() {
+        cmd.env("TZ", tz);
+    }
+    cmd.arg("--date=format-local:%Y-%m-%dT%H:%M:%S%z");
+    // Record separator before each commit, unit separators between fields; shortstat follows the body
+    cmd.arg("--format=%x1e%H%x1f%h%x1f%an%x1f%ae%x1f%ad%x1f%s%x1f%b%x1f");
+    cmd.arg("--shortstat");
+    add_author_filter(&mut cmd, filter_by_user, authors);
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git log failed in {}: {}",
+            repo.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.split('\x1e').filter(|r| !r.trim().is_empty()).filter_map(parse_commit_record).collect())
+}
+
+fn parse_commit_record(record: &str) -> Option<CommitRecord> {
+    let fields: Vec<&str> = record.splitn(8, '\x1f').collect();
+    if fields.len() < 8 {
+        return None;
+    }
+    Some(CommitRecord {
+        hash: fields[0].to_string(),
+        short_hash: fields[1].to_string(),
+        author_name: fields[2].to_string(),
+        author_email: fields[3].to_string(),
+        date: rfc3339_offset(fields[4]),
+        subject: fields[5].to_string(),
+        body: fields[6].trim().to_string(),
+        stats: parse_shortstat(fields[7]),
+    })
+}
+
+// git's strftime has no `%:z`; turn `+0200` into `+02:00`
+fn rfc3339_offset(date: &str) -> String {
+    let date = date.trim();
+    match date.len().checked_sub(5).map(|i| date.split_at(i)) {
+        Some((head, tz)) if tz.starts_with(['+', '-']) => format!("{}{}:{}", head, &tz[..3], &tz[3..]),
+        _ => date.to_string(),
+    }
+}
+
+/// Parses e.g. ` 3 files changed, 10 insertions(+), 2 deletions(-)`.
+fn parse_shortstat(text: &str) -> CommitStats {
+    let mut stats = CommitStats::default();
+    for part in text.trim().split(',') {
+        let mut words = part.split_whitespace();
+        let (Some(count), Some(kind)) = (words.next().and_then(|n| n.parse().ok()), words.next()) else {
+            continue;
+        };
+        if kind.starts_with("file") {
+            stats.files_changed = count;
+        } else if kind.starts_with("insertion") {
+            stats.insertions = count;
+        } else if kind.starts_with("deletion") {
+            stats.deletions = count;
+        }
+    }
+    stats
+}
+
 pub fn get_commit_details(repo: &PathBuf, commit_hash: &str) -> Result<String> {
     let output = Command::new("git")
         .arg("-C").arg(repo)
@@ -117,19 +220,102 @@ pub type CommitData = Vec<(PathBuf, Vec<String>)>;
 
 pub fn reload_commits(
     repos: &Vec<PathBuf>,
-    duration: Duration,
+    range: &TimeRange,
     filter_by_user: bool,
     detailed: bool,
-    from: Option<String>,
-    to: Option<String>,
+    authors: &[String],
 ) -> Result<CommitData> {
     let mut commits = vec![];
     for repo in repos {
         let repo_commits =
-            get_recent_commits(repo, duration, filter_by_user, detailed, from.clone(), to.clone())?;
+            get_recent_commits(repo, range, filter_by_user, detailed, authors)?;
         if !repo_commits.is_empty() {
             commits.push((repo.clone(), repo_commits));
         }
     }
     Ok(commits)
-}
\ No newline at end of file
+}
+/// An author with commits in the current range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorCount {
+    pub name: String,
+    pub email: String,
+    pub commits: usize,
+}
+
+/// Lists everyone with commits in `range` across all repos, most active first.
+pub fn collect_authors(repos: &[PathBuf], range: &TimeRange) -> Result<Vec<AuthorCount>> {
+    let mut authors: Vec<AuthorCount> = vec![];
+    for repo in repos {
+        let output = Command::new("git")
+            .arg("-C").arg(repo)
+            .arg("shortlog")
+            .arg("-sne")
+            .arg("--since").arg(range.git_since())
+            .arg("--until").arg(range.git_until())
+            .arg("HEAD")
+            .output()?;
+        // Repos without commits (unborn HEAD) simply contribute nobody
+        if !output.status.success() {
+            continue;
+        }
+        // Lines look like "     6\tJane Doe <jane@example.com>"
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((count, who)) = line.trim().split_once('\t') else { continue };
+            let count = count.trim().parse::<usize>().unwrap_or(0);
+            let (name, email) = match who.rsplit_once(" <") {
+                Some((name, email)) => (name.to_string(), email.trim_end_matches('>').to_string()),
+                None => (who.to_string(), String::new()),
+            };
+            match authors.iter_mut().find(|a| a.email == email) {
+                Some(existing) => existing.commits += count,
+                None => authors.push(AuthorCount { name, email, commits: count }),
+            }
+        }
+    }
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+    Ok(authors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picked_authors_match_whole_emails() {
+        let mut cmd = Command::new("git");
+        add_author_filter(&mut cmd, false, &["al@example.com".to_string(), "bo@example.com".to_string()]);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, ["--fixed-strings", "--author=<al@example.com>", "--author=<bo@example.com>"]);
+    }
+
+    fn stats(files_changed: u32, insertions: u32, deletions: u32) -> CommitStats {
+        CommitStats { files_changed, insertions, deletions }
+    }
+
+    #[test]
+    fn parses_shortstat_lines() {
+        assert_eq!(parse_shortstat(" 3 files changed, 10 insertions(+), 2 deletions(-)"), stats(3, 10, 2));
+        assert_eq!(parse_shortstat(" 1 file changed, 1 insertion(+)"), stats(1, 1, 0));
+        assert_eq!(parse_shortstat(" 2 files changed, 5 deletions(-)\n"), stats(2, 0, 5));
+    }
+
+    #[test]
+    fn shortstat_ignores_what_it_does_not_know() {
+        assert_eq!(parse_shortstat(""), CommitStats::default());
+        assert_eq!(parse_shortstat("binary, many files changed, 4 insertions(+)"), stats(0, 4, 0));
+    }
+
+    #[test]
+    fn adds_a_colon_to_the_offset() {
+        assert_eq!(rfc3339_offset("2024-05-01T10:00:00+0200"), "2024-05-01T10:00:00+02:00");
+        assert_eq!(rfc3339_offset(" 2024-05-01T10:00:00-0530 "), "2024-05-01T10:00:00-05:30");
+    }
+
+    #[test]
+    fn leaves_other_dates_alone() {
+        assert_eq!(rfc3339_offset("2024-05-01T10:00:00+02:00"), "2024-05-01T10:00:00+02:00");
+        assert_eq!(rfc3339_offset("2024-05-01T10:00:00Z"), "2024-05-01T10:00:00Z");
+        assert_eq!(rfc3339_offset("+02"), "+02");
+    }
+}
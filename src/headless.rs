@@ -0,0 +1,316 @@
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use tokio::runtime::Runtime;
+use std::io::{ErrorKind, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use crate::config::{self, ForgeConfig, JournalConfig, PublishTarget, TeamMember, TrackerConfig};
+use crate::forge::{fetch_activity, forge_remotes};
+use crate::export::{export_target, write_export, ExportDoc, ExportFormat};
+use crate::git::reload_commits;
+use crate::journal::write_journal;
+use crate::history::HistoryEntry;
+use crate::log::collect_log;
+use crate::notes::DayNotes;
+use crate::presets::Preset;
+use crate::prompts::PromptVars;
+use crate::network::{is_error_text, Provider};
+use crate::offline::offline_summary;
+use crate::range::TimeRange;
+use crate::redact::Redactor;
+use crate::team::{collect_team_commits, TeamReport, TeamReportMode};
+use crate::tickets::{refresh, tickets_in, tracker_from_config, TicketCache};
+use crate::remote::CommitLinker;
+use crate::structured::{self, StructuredSummary};
+use crate::utils::{format_commits_for_prompt, CommitData};
+
+/// Settings shared by all headless commands, resolved the same way as for the TUI.
+pub struct HeadlessContext {
+    pub repos: Vec<PathBuf>,
+    pub time_range: TimeRange,
+    pub lang: String,
+    pub model: String,
+    pub prompt_path: Option<String>,
+    /// Prompt preset for summaries; team reports use `prompt_path`.
+    pub preset: Preset,
+    pub tracker: Option<TrackerConfig>,
+    pub forge: Option<ForgeConfig>,
+    /// Set when `embed_commit_links` is on.
+    pub commit_links: Option<CommitLinker>,
+    /// Response cache TTL, and whether `--no-cache` bypasses it.
+    pub cache_hours: u64,
+    pub refresh: bool,
+    /// Masks prompts before they are sent.
+    pub redactor: Redactor,
+    pub provider: Provider,
+    /// Ask for a structured summary, see `structured_summary`.
+    pub structured: bool,
+}
+
+/// Whether summaries are built offline; says so when that is a fallback.
+fn use_offline(provider: Provider) -> bool {
+    let offline = provider.effective() == Provider::Offline;
+    if offline && provider == Provider::Gemini {
+        eprintln!("No Gemini API key found, using the offline summary.");
+    }
+    offline
+}
+
+/// Prints an AI summary, or a team report when `team` is given.
+pub fn run_summary(
+    ctx: &HeadlessContext,
+    all: bool,
+    team: Option<(&[TeamMember], TeamReportMode)>,
+    publish_to: &[PublishTarget],
+    yes: bool,
+) -> Result<()> {
+    let rt = Runtime::new()?;
+    let from = ctx.time_range.start_date();
+    let to = ctx.time_range.end_date();
+
+    if let Some((members, mode)) = team {
+        if members.is_empty() {
+            return Err(anyhow!("No team configured. Add [[team]] entries to {}", config::get_user_config_path().display()));
+        }
+        let offline = use_offline(ctx.provider);
+        let member_commits = collect_team_commits(&ctx.repos, &ctx.time_range, false, members)?;
+        let report = TeamReport {
+            mode,
+            from,
+            to,
+            interval: ctx.time_range.label.clone(),
+            lang: ctx.lang.clone(),
+            model: ctx.model.clone(),
+            prompt_path: ctx.prompt_path.clone(),
+            cache_hours: ctx.cache_hours,
+            refresh: ctx.refresh,
+            redactor: ctx.redactor.clone(),
+            offline,
+        };
+        let text = rt.block_on(report.generate(&member_commits));
+        let repos = ctx.repos.iter().map(|r| r.file_name().unwrap_or_default().to_string_lossy().to_string()).collect();
+        HistoryEntry::new("team", "Team", &ctx.time_range, repos, report.provider_model(), &ctx.lang, None).archive(&text);
+        print_line(&text)?;
+        let doc = ExportDoc::new(&ctx.time_range, Some(text), &CommitData::new(), false, None);
+        return publish_summary(&rt, &doc, publish_to, yes);
+    }
+
+    let commits = reload_commits(&ctx.repos, &ctx.time_range, !all, false, &[])?;
+    if commits.is_empty() {
+        return print_line(format_args!("No commits found in {}.", ctx.time_range.label));
+    }
+    let (summary, sections) = summarize(&rt, ctx, &commits, !all)?;
+    print_line(&summary)?;
+    let mut doc = ExportDoc::new(&ctx.time_range, Some(summary), &commits, !all, ctx.commit_links.as_ref());
+    doc.sections = sections;
+    publish_summary(&rt, &doc, publish_to, yes)
+}
+
+/// Resolves `--publish`: an empty name means every configured target.
+pub fn publish_targets(targets: &[PublishTarget], name: &str) -> Result<Vec<PublishTarget>> {
+    if targets.is_empty() {
+        return Err(anyhow!("No publish targets configured. Add [[publish]] entries to {}", config::get_user_config_path().display()));
+    }
+    if name.is_empty() {
+        return Ok(targets.to_vec());
+    }
+    targets.iter()
+        .find(|t| t.name.eq_ignore_ascii_case(name))
+        .map(|t| vec![t.clone()])
+        .ok_or_else(|| anyhow!("Unknown publish target '{}'", name))
+}
+
+/// Asks for confirmation on the terminal (unless `yes`), then posts to each target.
+fn publish_summary(rt: &Runtime, doc: &ExportDoc, targets: &[PublishTarget], yes: bool) -> Result<()> {
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let names = targets.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ");
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(anyhow!("Not publishing without confirmation; pass --yes to publish non-interactively"));
+        }
+        eprint!("Publish to {}? [y/N] ", names);
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            eprintln!("Not published.");
+            return Ok(());
+        }
+    }
+    for target in targets {
+        rt.block_on(crate::publish::publish(target, doc))?;
+        eprintln!("Published to {}", target.name);
+    }
+    Ok(())
+}
+
+/// Writes the commit list, plus an AI summary if asked, and prints the path.
+pub fn run_export(ctx: &HeadlessContext, pattern: &str, format: Option<ExportFormat>, all: bool, with_summary: bool, force: bool) -> Result<()> {
+    // Checked up front, so no summary is generated for nothing
+    let (target, _) = export_target(pattern, format, &ctx.time_range);
+    if !force && target.exists() {
+        return Err(anyhow!("{} already exists. Pass --force to overwrite it.", target.display()));
+    }
+    let commits = reload_commits(&ctx.repos, &ctx.time_range, !all, false, &[])?;
+    let (summary, sections) = if with_summary && !commits.is_empty() {
+        let rt = Runtime::new()?;
+        let (summary, sections) = summarize(&rt, ctx, &commits, !all)?;
+        (Some(summary), sections)
+    } else {
+        (None, None)
+    };
+    let mut doc = ExportDoc::new(&ctx.time_range, summary, &commits, !all, ctx.commit_links.as_ref());
+    doc.sections = sections;
+    let path = write_export(pattern, format, &doc, &ctx.time_range, force)?;
+    print_line(path.display())?;
+    Ok(())
+}
+
+/// Writes the summary into the daily note, or the commit list when
+/// `commits_only` is set, and prints the note's path.
+pub fn run_journal(ctx: &HeadlessContext, journal: Option<&JournalConfig>, all: bool, commits_only: bool) -> Result<()> {
+    let journal = journal.ok_or_else(|| anyhow!("No journal configured. Add a [journal] table to {}", config::get_user_config_path().display()))?;
+    let commits = reload_commits(&ctx.repos, &ctx.time_range, !all, false, &[])?;
+    if commits.is_empty() {
+        return print_line(format_args!("No commits found in {}.", ctx.time_range.label));
+    }
+    let (summary, sections) = if commits_only {
+        (None, None)
+    } else {
+        let (summary, sections) = summarize(&Runtime::new()?, ctx, &commits, !all)?;
+        (Some(summary), sections)
+    };
+    let mut doc = ExportDoc::new(&ctx.time_range, summary, &commits, !all, ctx.commit_links.as_ref());
+    doc.sections = sections;
+    let path = write_journal(journal, &ctx.time_range, &doc)?;
+    print_line(path.display())?;
+    Ok(())
+}
+
+/// Prints the commits of the range, as JSON or one line per commit.
+pub fn run_log(ctx: &HeadlessContext, json: bool, all: bool, authors: &[String]) -> Result<()> {
+    let only_mine = !all && authors.is_empty();
+    let log = collect_log(&ctx.repos, &ctx.time_range, only_mine, authors)?;
+    if json {
+        return print_line(serde_json::to_string_pretty(&log)?);
+    }
+    let mut out = std::io::stdout().lock();
+    let written = log.repos.iter()
+        .flat_map(|repo| repo.commits.iter().map(move |commit| (repo, commit)))
+        .try_for_each(|(repo, commit)| {
+            writeln!(out, "{}\t{}\t{}\t{}\t{}", repo.name, commit.short_hash, commit.date, commit.author.name, commit.subject)
+        });
+    ignore_broken_pipe(written.and_then(|_| out.flush()))
+}
+
+/// Prints a line to stdout.
+fn print_line(text: impl std::fmt::Display) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    ignore_broken_pipe(writeln!(out, "{}", text).and_then(|_| out.flush()))
+}
+
+/// A reader that went away, as in `whid log | head`, is not an error.
+fn ignore_broken_pipe(result: std::io::Result<()>) -> Result<()> {
+    match result {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// `filter_by_user` tells how the commit lines were loaded, with or without authors.
+/// Returns the summary text and, for a valid structured summary, its sections.
+fn summarize(rt: &Runtime, ctx: &HeadlessContext, commits: &CommitData, filter_by_user: bool) -> Result<(String, Option<StructuredSummary>)> {
+    let from = ctx.time_range.start_date();
+    let to = ctx.time_range.end_date();
+    let linked_commits;
+    let prompt_commits = match &ctx.commit_links {
+        Some(links) => {
+            linked_commits = links.annotate(commits);
+            &linked_commits
+        }
+        None => commits,
+    };
+    let commit_str = format_commits_for_prompt(prompt_commits);
+    let (ticket_details, tickets) = match &ctx.tracker {
+        Some(tracker_config) => {
+            let tracker = tracker_from_config(tracker_config)?;
+            let keys = tickets_in(commits, &tracker_config.projects);
+            let cache = Arc::new(Mutex::new(TicketCache::load()));
+            rt.block_on(refresh(tracker.as_ref(), &cache, &keys, tracker_config.cache_hours));
+            let cache = cache.lock().unwrap();
+            (cache.details(&keys), cache.infos(&keys))
+        }
+        None => (String::new(), vec![]),
+    };
+    let forge_activity = match &ctx.forge {
+        Some(forge_config) => {
+            let remotes = forge_remotes(&ctx.repos, forge_config);
+            let (items, errors) = rt.block_on(fetch_activity(forge_config, &remotes, &ctx.time_range));
+            for error in errors {
+                eprintln!("Forge: {}", error);
+            }
+            crate::forge::ForgeActivity { items, ..Default::default() }.for_prompt(None)
+        }
+        None => String::new(),
+    };
+    let notes = DayNotes::load(ctx.time_range.zone.today());
+    let vars = PromptVars {
+        from: &from,
+        to: &to,
+        project: "All projects",
+        interval: &ctx.time_range.label,
+        lang: &ctx.lang,
+        commits: &commit_str,
+        authors: &[],
+        ticket_details: &ticket_details,
+        forge_activity: &forge_activity,
+        plans: &notes.plans,
+        blockers: &notes.blockers,
+        commit_data: prompt_commits,
+        filter_by_user,
+        tickets: &tickets,
+    };
+    if use_offline(ctx.provider) {
+        let summary = offline_summary(&vars);
+        HistoryEntry::new("summary", "All projects", &ctx.time_range, repo_names(commits), "offline", &ctx.lang, None).archive(&summary);
+        return Ok((summary, None));
+    }
+    let redacted = ctx.redactor.redact_vars(&vars, &repo_names(commits));
+    if let Some(masked) = redacted.masked.summary() {
+        eprintln!("Masked before sending: {}", masked);
+    }
+    let mut prompt = ctx.preset.build_prompt(&redacted.vars(&vars))?;
+    if ctx.structured {
+        prompt.push_str(structured::INSTRUCTIONS);
+    }
+    let summary = match rt.block_on(crate::network::fetch_summary_cached(&prompt, &ctx.lang, &ctx.model, ctx.cache_hours, ctx.refresh, ctx.structured)) {
+        Ok((summary, _)) if !is_error_text(&summary) => summary,
+        Ok((error, _)) => return Ok((offline_fallback(&error, &vars), None)),
+        Err(e) => return Ok((offline_fallback(&format!("Gemini error: {}", e), &vars), None)),
+    };
+    let (summary, sections) = if ctx.structured {
+        match structured::parse(&summary) {
+            Ok(sections) => (sections.to_markdown(), Some(sections)),
+            Err(e) => {
+                eprintln!("Structured summary invalid ({}), using the text.", e);
+                (summary, None)
+            }
+        }
+    } else {
+        (summary, None)
+    };
+    HistoryEntry::new("summary", "All projects", &ctx.time_range, repo_names(commits), &ctx.model, &ctx.lang, Some(&prompt))
+        .archive(&summary);
+    Ok((summary, sections))
+}
+
+fn offline_fallback(error: &str, vars: &PromptVars) -> String {
+    eprintln!("{}", error.lines().next().unwrap_or_default());
+    eprintln!("Using the offline summary instead.");
+    offline_summary(vars)
+}
+
+fn repo_names(commits: &CommitData) -> Vec<String> {
+    commits.iter().map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string()).collect()
+}
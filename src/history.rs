@@ -0,0 +1,177 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
+use crate::range::TimeRange;
+
+/// One generated summary with what it was generated from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    /// Creation time in milliseconds with the process id and a counter, so
+    /// summaries finishing in the same millisecond get their own files. Also the file name.
+    pub id: String,
+    /// RFC 3339 timestamp.
+    pub created_at: String,
+    /// "summary" or "team"
+    pub kind: String,
+    pub project: String,
+    pub interval: String,
+    pub from: String,
+    pub to: String,
+    pub repos: Vec<String>,
+    pub model: String,
+    pub lang: String,
+    /// SHA-256 of the prompt sent; team reports send several and have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_hash: Option<String>,
+    pub text: String,
+}
+
+pub fn prompt_hash(prompt: &str) -> String {
+    format!("{:x}", Sha256::digest(prompt.as_bytes()))
+}
+
+impl HistoryEntry {
+    /// A new entry for `range`; `text` is filled in once the summary arrives.
+    pub fn new(kind: &str, project: &str, range: &TimeRange, repos: Vec<String>, model: &str, lang: &str, prompt: Option<&str>) -> Self {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let now = chrono::Local::now();
+        HistoryEntry {
+            id: format!("{}-{}-{:04}", now.timestamp_millis(), std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)),
+            created_at: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            kind: kind.to_string(),
+            project: project.to_string(),
+            interval: range.label.clone(),
+            from: range.start_date(),
+            to: range.end_date(),
+            repos,
+            model: model.to_string(),
+            lang: lang.to_string(),
+            prompt_hash: prompt.map(prompt_hash),
+            text: String::new(),
+        }
+    }
+
+    fn dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("whid").join("history"))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = Self::dir().ok_or_else(|| anyhow!("No data directory"))?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.json", self.id)), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Stores `text` as the summary unless it is an error message.
+    pub fn archive(mut self, text: &str) {
+        if crate::network::is_error_text(text) {
+            return;
+        }
+        self.text = text.to_string();
+        let _ = self.save();
+    }
+
+    /// All archived summaries, newest first. Unreadable files are skipped.
+    pub fn load_all() -> Vec<HistoryEntry> {
+        Self::dir().map(|dir| Self::load_dir(&dir)).unwrap_or_default()
+    }
+
+    fn load_dir(dir: &Path) -> Vec<HistoryEntry> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+        let mut all: Vec<HistoryEntry> = entries
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|e| fs::read_to_string(e.path()).ok())
+            .filter_map(|content| serde_json::from_str(&content).ok())
+            .collect();
+        all.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        all
+    }
+
+    /// Case-insensitive match on the metadata and the summary text.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        [&self.project, &self.interval, &self.from, &self.to, &self.model, &self.kind, &self.text]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+            || self.repos.iter().any(|r| r.to_lowercase().contains(&query))
+    }
+
+    /// One-line description for the history list.
+    pub fn label(&self) -> String {
+        let range = if self.from == self.to { self.to.clone() } else { format!("{} – {}", self.from, self.to) };
+        format!("{}  {}  {} ({})  {}", self.created_at.get(..16).unwrap_or(&self.created_at).replace('T', " "), self.project, range, self.interval, self.model)
+    }
+}
+
+/// Line diff from `old` to `new`, for showing two summaries side by side in time.
+pub fn diff_lines(old: &str, new: &str) -> Vec<(ChangeTag, String)> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| (change.tag(), change.value().trim_end_matches('\n').to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::{DateBounds, Interval, Zone};
+
+    fn entry(project: &str, created_at: &str, text: &str) -> HistoryEntry {
+        let bounds = DateBounds::parse(Some("2024-05-01"), Some("2024-05-02")).unwrap();
+        let range = TimeRange::resolve(&bounds, &Interval::rolling("24h", 24), Zone::parse("UTC").unwrap());
+        let mut entry = HistoryEntry::new("summary", project, &range, vec!["web-app".to_string()], "gemini-2.5-flash", "en", Some("prompt"));
+        entry.created_at = created_at.to_string();
+        entry.text = text.to_string();
+        entry
+    }
+
+    #[test]
+    fn ids_are_unique_within_a_millisecond() {
+        let ids: std::collections::HashSet<String> = (0..100).map(|_| entry("web", "", "").id).collect();
+        assert_eq!(ids.len(), 100);
+    }
+
+    #[test]
+    fn matches_metadata_and_text_ignoring_case() {
+        let entry = entry("Billing", "2024-05-02T18:00:00+02:00", "Fixed the **login** redirect");
+        for query in ["billing", "WEB-APP", "2024-05-01", "flash", "Login", "summary"] {
+            assert!(entry.matches(query), "{}", query);
+        }
+        assert!(!entry.matches("payroll"));
+    }
+
+    #[test]
+    fn loads_newest_first_and_skips_unreadable_files() {
+        let dir = std::env::temp_dir().join(format!("whid-history-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for entry in [entry("old", "2024-05-01T09:00:00+02:00", "a"), entry("new", "2024-05-02T09:00:00+02:00", "b")] {
+            fs::write(dir.join(format!("{}.json", entry.id)), serde_json::to_string(&entry).unwrap()).unwrap();
+        }
+        fs::write(dir.join("broken.json"), "{ not json").unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        let projects: Vec<String> = HistoryEntry::load_dir(&dir).into_iter().map(|e| e.project).collect();
+        assert_eq!(projects, ["new", "old"]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(HistoryEntry::load_dir(&dir).is_empty());
+    }
+
+    #[test]
+    fn diffs_line_by_line() {
+        let diff = diff_lines("- login\n- search\n", "- login\n- export\n");
+        assert_eq!(
+            diff,
+            [
+                (ChangeTag::Equal, "- login".to_string()),
+                (ChangeTag::Delete, "- search".to_string()),
+                (ChangeTag::Insert, "- export".to_string()),
+            ]
+        );
+    }
+}
@@ -1,21 +1,42 @@
-use std::{sync::{Arc, Mutex}, time::Duration, path::PathBuf};
+use std::{sync::{Arc, Mutex}, path::PathBuf};
 use crossterm::event::{KeyCode, MouseEvent, MouseEventKind};
 use tokio::runtime::Runtime;
 use arboard::Clipboard;
 use crate::models::FocusArea;
-use crate::models::PopupQuote;
-use crate::git::reload_commits;
-use crate::utils::{get_active_commits, CommitData};
+use crate::models::{ChatTurn, PopupQuote, PromptPreview, SummarySections};
+use crate::git::{collect_authors, reload_commits};
+use crate::utils::{commit_hash, filter_commits, format_commits_for_prompt, get_active_commits, selected_commit, CommitData};
+use crate::prompts::PromptVars;
 use anyhow::Result;
 use crate::models::SelectedCommits;
+use crate::range::{DateBounds, Interval, TimeRange};
+use crate::models::{AuthorPicker, DateRangeDialog, ExportDialog, HistoryView, NotesEditor, NotesField, PresetPicker, PublishDialog, SearchState};
+use crate::presets::{find, Preset, PresetDefaults, PresetSource};
+use crate::redact::Redactor;
+use crate::network::{is_error_text, Provider};
+use crate::offline::offline_summary;
+use crate::notes::DayNotes;
+use crate::editor::TextEditor;
+use crate::history::{diff_lines, prompt_hash, HistoryEntry};
+use crate::config::{JournalConfig, PublishTarget, TeamMember};
+use crate::export::{export_target, write_export, ExportDoc};
+use crate::journal::write_journal;
+use crate::tickets::{tickets_in, TicketCache};
+use crate::forge::ForgeActivity;
+use crate::remote::{open_in_browser, CommitLinker};
+use crate::structured;
+use crate::team::{collect_team_commits, TeamReport, TeamReportMode};
+use chrono::{Days, Months};
+use std::future::Future;
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_key(
     key: KeyCode,
-    intervals: &[(&str, Duration)],
+    intervals: &[Interval],
     current_index: &mut usize,
-    current_interval: &mut Duration,
     filter_by_user: &mut bool,
     repos: &Vec<PathBuf>,
+    loaded_commits: &mut CommitData,
     commits: &mut CommitData,
     selected_repo_index: &mut usize,
     selected_commit_index: &mut Option<usize>,
@@ -32,11 +53,385 @@ pub fn handle_key(
     prompt_path: Option<&str>, // <-- add prompt_path argument
     gemini_model: &str, // <-- add gemini_model argument
     detailed_commit_view: &mut bool, // <-- add new argument
-    from_date: Option<String>,
-    to_date: Option<String>,
+    date_bounds: &mut DateBounds,
+    time_range: &mut TimeRange,
+    range_dialog: &mut DateRangeDialog,
+    search: &mut SearchState,
+    author_picker: &mut AuthorPicker,
+    team: &[TeamMember],
+    team_mode: TeamReportMode,
+    export_path: &str,
+    export_dialog: &mut ExportDialog,
+    publish_targets: &[PublishTarget],
+    publish_dialog: &mut PublishDialog,
+    ticket_cache: &Arc<Mutex<TicketCache>>,
+    ticket_projects: Option<&[String]>,
+    forge_activity: &Arc<Mutex<ForgeActivity>>,
+    commit_links: &CommitLinker,
+    embed_commit_links: bool,
+    journal: Option<&JournalConfig>,
+    notes_editor: &mut NotesEditor,
+    history: &mut HistoryView,
+    cache_hours: u64,
+    presets: &[Preset],
+    preset_defaults: &PresetDefaults,
+    preset_picker: &mut PresetPicker,
+    preview_prompt: bool,
+    redactor: &Redactor,
+    provider: Provider,
+    structured_summary: bool,
 ) -> Result<bool> {
     let lang = if lang.is_empty() { "english" } else { lang };
+    // Picking a preset requests the summary like `A` does
+    let key = if preset_picker.visible {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => preset_picker.cursor = preset_picker.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if preset_picker.cursor + 1 < presets.len() => {
+                preset_picker.cursor += 1;
+            }
+            KeyCode::Enter => {
+                preset_picker.visible = false;
+                preset_picker.chosen = presets.get(preset_picker.cursor).map(|p| p.name.clone());
+            }
+            KeyCode::Esc | KeyCode::Char('q') => preset_picker.visible = false,
+            _ => {}
+        }
+        if key != KeyCode::Enter {
+            return Ok(true);
+        }
+        KeyCode::Char('A')
+    } else {
+        key
+    };
+    if range_dialog.visible {
+        if let Some(bounds) = handle_range_dialog_key(key, range_dialog) {
+            *date_bounds = bounds;
+            *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+            *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
+            *selected_commit_index = None;
+            if *selected_repo_index != usize::MAX && *selected_repo_index >= commits.len() {
+                *selected_repo_index = usize::MAX;
+            }
+        }
+        return Ok(true);
+    }
+    if author_picker.visible {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => author_picker.cursor = author_picker.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if author_picker.cursor + 1 < author_picker.authors.len() => { author_picker.cursor += 1; }
+            KeyCode::Char(' ') | KeyCode::Char('x') => {
+                if let Some(author) = author_picker.authors.get(author_picker.cursor) && !author_picker.pending.remove(&author.email) {
+                    author_picker.pending.insert(author.email.clone());
+                }
+            }
+            KeyCode::Char('c') => author_picker.pending.clear(),
+            KeyCode::Enter => {
+                // Keep the panel's order (most active first) for the applied list
+                author_picker.selected = author_picker.authors.iter()
+                    .filter(|a| author_picker.pending.contains(&a.email))
+                    .map(|a| a.email.clone())
+                    .collect();
+                author_picker.visible = false;
+                if !author_picker.selected.is_empty() {
+                    *filter_by_user = false;
+                }
+                *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+                *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+                *commits = filter_commits(loaded_commits, search, *filter_by_user);
+                *selected_commit_index = None;
+                if *selected_repo_index != usize::MAX && *selected_repo_index >= commits.len() {
+                    *selected_repo_index = usize::MAX;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('p') => author_picker.visible = false,
+            _ => {}
+        }
+        return Ok(true);
+    }
+    if publish_dialog.visible {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => publish_dialog.cursor = publish_dialog.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') if publish_dialog.cursor + 1 < publish_targets.len() => {
+                publish_dialog.cursor += 1;
+            }
+            KeyCode::Enter | KeyCode::Char('y') => {
+                publish_dialog.visible = false;
+                if let Some(target) = publish_targets.get(publish_dialog.cursor).cloned() {
+                    let (summary, sections) = {
+                        let mut p = popup_quote.lock().unwrap();
+                        p.status = Some(format!("Publishing to {}...", target.name));
+                        (p.text.clone(), p.shown_sections().map(|s| s.summary.clone()))
+                    };
+                    let mut doc = ExportDoc::new(time_range, Some(summary), commits, *filter_by_user, embed_commit_links.then_some(commit_links));
+                    doc.sections = sections;
+                    let popup = popup_quote.clone();
+                    rt.spawn(async move {
+                        let status = match crate::publish::publish(&target, &doc).await {
+                            Ok(()) => format!("Published to {}", target.name),
+                            Err(e) => format!("Publishing failed: {}", e),
+                        };
+                        popup.lock().unwrap().status = Some(status);
+                    });
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => publish_dialog.visible = false,
+            _ => {}
+        }
+        return Ok(true);
+    }
+    if export_dialog.visible {
+        match key {
+            KeyCode::Char(c) => {
+                export_dialog.path.push(c);
+                export_dialog.confirm_overwrite = false;
+            }
+            KeyCode::Backspace => {
+                export_dialog.path.pop();
+                export_dialog.confirm_overwrite = false;
+            }
+            // Back to editing the path, or cancel
+            KeyCode::Esc if export_dialog.confirm_overwrite => export_dialog.confirm_overwrite = false,
+            KeyCode::Esc => export_dialog.visible = false,
+            KeyCode::Enter if !export_dialog.confirm_overwrite && export_target(&export_dialog.path, None, time_range).0.exists() => {
+                export_dialog.confirm_overwrite = true;
+            }
+            KeyCode::Enter => {
+                export_dialog.visible = false;
+                // With the summary open, export it together with the list; otherwise just the list
+                let mut p = popup_quote.lock().unwrap();
+                let summary = if p.visible { Some(p.text.clone()) } else { None };
+                let mut doc = ExportDoc::new(time_range, summary, commits, *filter_by_user, embed_commit_links.then_some(commit_links));
+                if p.visible {
+                    doc.sections = p.shown_sections().map(|s| s.summary.clone());
+                }
+                let status = match write_export(&export_dialog.path, None, &doc, time_range, export_dialog.confirm_overwrite) {
+                    Ok(path) => format!("Exported {} commits to {}", doc.commits.len(), path.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+                if !p.visible {
+                    p.visible = true;
+                    p.scroll = 0;
+                    p.start_conversation(None);
+                    p.text = status.clone();
+                }
+                p.status = Some(status);
+            }
+            _ => {}
+        }
+        return Ok(true);
+    }
+    if history.visible {
+        handle_history_key(key, history, popup_quote);
+        return Ok(true);
+    }
+    if notes_editor.visible {
+        let field = match notes_editor.field {
+            NotesField::Plans => &mut notes_editor.notes.plans,
+            NotesField::Blockers => &mut notes_editor.notes.blockers,
+        };
+        match key {
+            KeyCode::Char(c) => field.push(c),
+            KeyCode::Enter => field.push('\n'),
+            KeyCode::Backspace => { field.pop(); }
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                notes_editor.field = match notes_editor.field {
+                    NotesField::Plans => NotesField::Blockers,
+                    NotesField::Blockers => NotesField::Plans,
+                };
+            }
+            KeyCode::Esc => {
+                // Notes are saved on close; there is nothing to discard
+                notes_editor.visible = false;
+                let status = match notes_editor.notes.save(notes_editor.date) {
+                    Ok(()) => format!("Notes for {} saved", notes_editor.date),
+                    Err(e) => format!("Saving notes failed: {}", e),
+                };
+                popup_quote.lock().unwrap().status = Some(status);
+            }
+            _ => {}
+        }
+        return Ok(true);
+    }
+    {
+        let mut p = popup_quote.lock().unwrap();
+        if p.visible {
+            if let Some(preview) = p.preview.as_mut() {
+                if preview.editing {
+                    match key {
+                        KeyCode::Esc => preview.editing = false,
+                        _ => edit_text(&mut preview.editor, key),
+                    }
+                    return Ok(true);
+                }
+                match key {
+                    KeyCode::Up | KeyCode::Char('k') => preview.editor.up(),
+                    KeyCode::Down | KeyCode::Char('j') => preview.editor.down(),
+                    KeyCode::PageUp => (0..10).for_each(|_| preview.editor.up()),
+                    KeyCode::PageDown => (0..10).for_each(|_| preview.editor.down()),
+                    KeyCode::Char('d') | KeyCode::Delete => preview.editor.delete_line(),
+                    KeyCode::Char('i') => preview.editing = true,
+                    KeyCode::Char('E') => p.external_edit = true,
+                    KeyCode::Enter | KeyCode::Char('y') => {
+                        if let Some(preview) = p.preview.take() {
+                            drop(p);
+                            let prompt = preview.editor.text();
+                            send_summary(rt, popup_quote, prompt, preview.details, preview.fallback, preview.lang, preview.model, cache_hours, preview.refresh, structured_summary, preview.entry);
+                        }
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        p.preview = None;
+                        p.visible = false;
+                    }
+                    _ => {}
+                }
+                return Ok(true);
+            }
+            if let Some(editor) = p.editor.as_mut() {
+                match key {
+                    KeyCode::Esc => {
+                        // Keep the edits: copy, export and publish all read `text`
+                        let text = editor.text();
+                        if text != p.text {
+                            p.sections = None;
+                        }
+                        p.text = text;
+                        p.editor = None;
+                        p.status = Some("Summary updated".to_string());
+                    }
+                    _ => edit_text(editor, key),
+                }
+                return Ok(true);
+            }
+            if let Some(input) = p.chat_input.as_mut() {
+                match key {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => { input.pop(); },
+                    KeyCode::Esc => p.chat_input = None,
+                    KeyCode::Enter => {
+                        let question = input.trim().to_string();
+                        p.chat_input = None;
+                        if !question.is_empty() {
+                            // The current answer becomes scrollback; edits to it are kept as context
+                            let answer = std::mem::take(&mut p.text);
+                            let previous = p.question.take();
+                            p.chat.push(ChatTurn { question: previous, answer });
+                            let history = p.chat_history();
+                            p.question = Some(question.clone());
+                            p.loading = true;
+                            p.spinner_frame = 0;
+                            p.cached = false;
+                            p.status = None;
+                            p.scroll_to_latest = true;
+                            drop(p);
+                            let gemini_model = gemini_model.to_string();
+                            spawn_popup_task(rt, popup_quote, async move {
+                                crate::network::fetch_gemini_chat(&history, &question, &gemini_model)
+                                    .await
+                                    .unwrap_or_else(|e| format!("Gemini error: {}", e))
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+                return Ok(true);
+            }
+            // Structured summaries move between sections; other keys act as usual
+            if let Some(sections) = p.shown_sections() {
+                match key {
+                    KeyCode::Up | KeyCode::Char('k') => sections.up(),
+                    KeyCode::Down | KeyCode::Char('j') => sections.down(),
+                    KeyCode::Char(' ') => sections.toggle(),
+                    KeyCode::Char('z') => sections.toggle_all(),
+                    _ => {}
+                }
+                if matches!(key, KeyCode::Up | KeyCode::Char('k') | KeyCode::Down | KeyCode::Char('j') | KeyCode::Char(' ') | KeyCode::Char('z')) {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    if search.active {
+        let mut query = search.query.clone();
+        match key {
+            KeyCode::Char(c) => query.push(c),
+            KeyCode::Backspace => { query.pop(); },
+            KeyCode::Enter => search.active = false,
+            KeyCode::Esc => {
+                search.active = false;
+                query.clear();
+            }
+            _ => {}
+        }
+        if query != search.query {
+            // Filter live while typing
+            search.set_query(query);
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
+            *selected_commit_index = None;
+            *commitlist_scroll = 0;
+            if *selected_repo_index != usize::MAX && *selected_repo_index >= commits.len() {
+                *selected_repo_index = usize::MAX;
+            }
+        }
+        return Ok(true);
+    }
+    {
+        let mut p = popup_quote.lock().unwrap();
+        if !p.visible {
+            p.status = None;
+        }
+    }
     match key {
+        KeyCode::Char('/') => {
+            search.active = true;
+            *focus = FocusArea::CommitList;
+            *selected_tab = crate::CommitTab::Timeframe;
+        },
+        KeyCode::Char('n') | KeyCode::Char('N') if search.is_filtering() => {
+            // Jump to the next/previous hit, wrapping around
+            let total = if *selected_repo_index == usize::MAX {
+                commits.iter().map(|(_, c)| c.len()).sum::<usize>()
+            } else {
+                commits.get(*selected_repo_index).map(|(_, c)| c.len()).unwrap_or(0)
+            };
+            if total > 0 {
+                *selected_commit_index = Some(match (*selected_commit_index, key) {
+                    (None, KeyCode::Char('n')) => 0,
+                    (None, _) => total - 1,
+                    (Some(idx), KeyCode::Char('n')) => (idx + 1) % total,
+                    (Some(idx), _) => (idx + total - 1) % total,
+                });
+                *focus = FocusArea::CommitList;
+                *selected_tab = crate::CommitTab::Timeframe;
+            }
+        },
+        KeyCode::Char('f') => {
+            search.filter_summary = !search.filter_summary;
+        },
+        KeyCode::Char('H') => {
+            history.entries = HistoryEntry::load_all();
+            history.cursor = 0;
+            history.query.clear();
+            history.searching = false;
+            history.marked = None;
+            history.diff = None;
+            history.message = None;
+            history.visible = true;
+        },
+        KeyCode::Char('b') => {
+            // Plans and blockers are kept per calendar day, for the standup given today
+            notes_editor.date = time_range.zone.today();
+            notes_editor.notes = DayNotes::load(notes_editor.date);
+            notes_editor.field = NotesField::Plans;
+            notes_editor.visible = true;
+        },
+        KeyCode::Char('p') => {
+            // Open the author picker with everyone active in the current range
+            author_picker.authors = collect_authors(repos, time_range)?;
+            author_picker.pending = author_picker.selected.iter().cloned().collect();
+            author_picker.cursor = 0;
+            author_picker.visible = true;
+        },
         KeyCode::Char('1') => {
             *focus = FocusArea::Sidebar;
             *selected_tab = crate::CommitTab::Timeframe;
@@ -50,9 +445,13 @@ pub fn handle_key(
             *selected_tab = crate::CommitTab::Selection;
         },
         KeyCode::Char('w') => {
-            *current_index = 3;
-            *current_interval = intervals[*current_index].1;
-            *commits = reload_commits(repos, *current_interval, *filter_by_user, *detailed_commit_view, from_date, to_date)?;
+            if let Some(week) = intervals.iter().position(Interval::is_week) {
+                *current_index = week;
+            }
+            *date_bounds = DateBounds::default();
+            *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+            *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
             *selected_commit_index = None;
             // After reloading commits (timeframe/filter change), ensure selected_repo_index is valid
             if *selected_repo_index != usize::MAX {
@@ -102,8 +501,11 @@ pub fn handle_key(
             } else {
                 *current_index = 0;
             }
-            *current_interval = intervals[*current_index].1;
-            *commits = reload_commits(repos, *current_interval, *filter_by_user, *detailed_commit_view, from_date.clone(), to_date.clone())?;
+            // Cycling intervals leaves any custom date range
+            *date_bounds = DateBounds::default();
+            *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+            *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
             *selected_commit_index = None;
             // After reloading commits (timeframe/filter change), ensure selected_repo_index is valid
             if *selected_repo_index != usize::MAX {
@@ -121,8 +523,10 @@ pub fn handle_key(
             } else {
                 *current_index = intervals.len() - 1;
             }
-            *current_interval = intervals[*current_index].1;
-            *commits = reload_commits(repos, *current_interval, *filter_by_user, *detailed_commit_view, from_date, to_date)?;
+            *date_bounds = DateBounds::default();
+            *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+            *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
             *selected_commit_index = None;
             // After reloading commits (timeframe/filter change), ensure selected_repo_index is valid
             if *selected_repo_index != usize::MAX {
@@ -133,27 +537,21 @@ pub fn handle_key(
                 }
             }
         },
-        KeyCode::Char(' ') => {
-            if *focus == FocusArea::CommitList {
-                // Ensure a commit is selected
-                if selected_commit_index.is_none() {
-                    if *selected_repo_index == usize::MAX {
-                        // global first commit
-                        if let Some((_, commits_list)) = commits.first() {
-                            if !commits_list.is_empty() {
-                                *selected_commit_index = Some(0);
-                            }
-                        }
-                    } else if let Some(repo_commits) = get_active_commits(commits, *selected_repo_index) {
-                        if !repo_commits.is_empty() {
-                            *selected_commit_index = Some(0);
-                        }
+        KeyCode::Char(' ') if *focus == FocusArea::CommitList => {
+            // Ensure a commit is selected
+            if selected_commit_index.is_none() {
+                if *selected_repo_index == usize::MAX {
+                    // global first commit
+                    if let Some((_, commits_list)) = commits.first() && !commits_list.is_empty() {
+                        *selected_commit_index = Some(0);
                     }
+                } else if let Some(repo_commits) = get_active_commits(commits, *selected_repo_index) && !repo_commits.is_empty() {
+                    *selected_commit_index = Some(0);
                 }
-                // Toggle detail view
-                *show_details = !*show_details;
-                if !*show_details { *focus = FocusArea::CommitList; }
             }
+            // Toggle detail view
+            *show_details = !*show_details;
+            if !*show_details { *focus = FocusArea::CommitList; }
         }
         KeyCode::Up | KeyCode::Char('k') => {
             // Popup scroll up
@@ -187,8 +585,7 @@ pub fn handle_key(
                             if commits.iter().map(|(_,c)|c.len()).sum::<usize>()>0 { *selected_commit_index = Some(0); }
                         }
                     } else {
-                        if let Some(idx)=*selected_commit_index {
-                            if idx>0 { *selected_commit_index = Some(idx-1); } }
+                        if let Some(idx)=*selected_commit_index && idx>0 { *selected_commit_index = Some(idx-1); }
                     }
                     *commitlist_scroll = (*selected_commit_index).unwrap_or(0).min(*commitlist_scroll);
                 }
@@ -201,7 +598,9 @@ pub fn handle_key(
             // Popup scroll down
             let mut popup = popup_quote.lock().unwrap();
             if popup.visible {
-                let text_lines = popup.text.lines().count() as u16;
+                // Earlier exchanges are shown above the latest answer
+                let text_lines = popup.chat.iter().map(|turn| turn.answer.lines().count() + 3).sum::<usize>() as u16
+                    + popup.text.lines().count() as u16;
                 // Estimate popup height (centered_rect(60,80,area)), minus title/footer
                 let area = crossterm::terminal::size().unwrap_or((120,40));
                 let popup_height = (area.1 as f32 * 0.8) as u16 - 4;
@@ -263,7 +662,13 @@ pub fn handle_key(
         }
         KeyCode::Char('u') => {
             *filter_by_user = !*filter_by_user;
-            *commits = reload_commits(repos, *current_interval, *filter_by_user, *detailed_commit_view, from_date, to_date)?;
+            // "Only mine" replaces any picked authors
+            if *filter_by_user {
+                author_picker.selected.clear();
+            }
+            *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+            *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
             *selected_commit_index=None;
             *detail_scroll=0;
             // After reloading commits (timeframe/filter change), ensure selected_repo_index is valid
@@ -276,153 +681,331 @@ pub fn handle_key(
             }
         }
         KeyCode::Char('q') => return Ok(false),
-        KeyCode::Char('a') | KeyCode::Char('A') => {
-            let (_prompt_template, debug_msg) = if let Some(path) = prompt_path {
-                match std::fs::read_to_string(path) {
-                    Ok(content) => (content, Some(format!("Prompt loaded from {}", path))),
-                    Err(e) => {
-                        let fallback = String::from("Custom prompt file could not be loaded.");
-                        (fallback, Some(format!("Error loading {}: {}. Falling back to default prompt.", path, e)))
-                    }
+        KeyCode::Char('a') if !popup_quote.lock().unwrap().loading => {
+            // Ask for the preset first, starting at the default for the selected repo
+            let repo = summary_repo_name(commits, *selected_repo_index, *selected_tab);
+            let preset = preset_defaults.resolve(presets, repo.as_deref());
+            preset_picker.cursor = presets.iter().position(|p| p.name == preset.name).unwrap_or(0);
+            preset_picker.visible = true;
+        }
+        KeyCode::Char('A') | KeyCode::Char('R') if !popup_quote.lock().unwrap().loading => {
+            // R regenerates: same preset and prompt, but the response cache is skipped
+            let refresh = key == KeyCode::Char('R');
+            let picked = if refresh { preset_picker.last.clone() } else { preset_picker.chosen.take() };
+            let preset = match picked.as_deref().and_then(|name| find(presets, name)) {
+                Some(preset) => preset,
+                None => {
+                    let repo = summary_repo_name(commits, *selected_repo_index, *selected_tab);
+                    preset_defaults.resolve(presets, repo.as_deref())
                 }
-            } else {
-                let fallback = String::from("No custom prompt file provided.");
-                (fallback, Some("".to_string()))
+            };
+            preset_picker.last = Some(preset.name.clone());
+            let preset_msg = match &preset.source {
+                PresetSource::File(path) if !path.exists() => format!("Preset: {} ({} not found, using the standup prompt)", preset.name, path.display()),
+                _ => format!("Preset: {} – {}", preset.name, preset.description),
             };
             // --- Gemini prompt construction update ---
-            use chrono::Local;
-            let now = Local::now();
-            let to_date = now.format("%Y-%m-%d").to_string();
-            let from_date = (now - *current_interval).format("%Y-%m-%d").to_string();
-            let interval_str = intervals[*current_index].0;
-            let (project_name, commit_str) = match selected_tab {
+            let from_date = time_range.start_date();
+            let to_date = time_range.end_date();
+            let interval_str = time_range.label.clone();
+            let author_names = author_picker.selected_names();
+            // The list may be narrowed by a search; only send that view when asked to
+            let summary_commits: &CommitData = if search.filter_summary { commits } else { loaded_commits };
+            let linked_commits;
+            let summary_commits = if embed_commit_links {
+                linked_commits = commit_links.annotate(summary_commits);
+                &linked_commits
+            } else {
+                summary_commits
+            };
+            let selected_repo = commits.get(*selected_repo_index).map(|(repo, _)| repo.clone());
+            let summary_repo = summary_commits.iter().find(|(repo, _)| Some(repo) == selected_repo.as_ref());
+            // The commits behind the prompt text, grouped by repo for templates
+            let (project_name, commit_str, prompt_commits): (String, String, CommitData) = match selected_tab {
                 crate::CommitTab::Timeframe => {
                     if (*selected_repo_index) == usize::MAX {
-                        let all_commits = commits.iter()
-                            .flat_map(|(repo, msgs)| {
-                                let repo_name = repo.file_name().unwrap_or_default().to_string_lossy();
-                                msgs.iter().map(move |msg| format!("[{}] {}", repo_name, msg))
-                            })
-                            .collect::<Vec<_>>()
-                            .join("\n");
-                        ("All projects".to_string(), all_commits)
+                        ("All projects".to_string(), format_commits_for_prompt(summary_commits), summary_commits.clone())
                     } else {
-                        let project = commits.get(*selected_repo_index)
+                        let project = summary_repo
                             .map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string())
                             .unwrap_or_else(|| "Project".to_string());
-                        let commitlist = commits.get(*selected_repo_index)
+                        let commitlist = summary_repo
                             .map(|(_repo, msgs)| msgs.join("\n"))
                             .unwrap_or_default();
-                        (project, commitlist)
+                        (project, commitlist, summary_repo.cloned().into_iter().collect())
                     }
                 }
                 crate::CommitTab::Selection => {
                     let sel = selected_commits.lock().unwrap();
                     let mut hash_to_commit = std::collections::HashMap::new();
-                    for (_repo, repo_commits) in commits.iter() {
+                    for (repo, repo_commits) in loaded_commits.iter() {
                         for commit in repo_commits {
                             if let Some(hash) = commit.split_whitespace().next() {
-                                hash_to_commit.insert(hash, commit);
+                                hash_to_commit.insert(hash, (repo, commit));
                             }
                         }
                     }
+                    let mut selection: CommitData = vec![];
                     let commit_lines: Vec<String> = sel.set.iter()
-                        .filter_map(|hash| hash_to_commit.get(hash.as_str()).map(|s| s.to_string()))
+                        .filter_map(|hash| hash_to_commit.get(hash.as_str()))
+                        .map(|(repo, commit)| {
+                            let line = match embed_commit_links.then(|| commit_links.commit_url(repo, commit_hash(commit))).flatten() {
+                                Some(url) => format!("{} <{}>", commit, url),
+                                None => commit.to_string(),
+                            };
+                            match selection.iter_mut().find(|(r, _)| r == *repo) {
+                                Some((_, lines)) => lines.push(line.clone()),
+                                None => selection.push(((*repo).clone(), vec![line.clone()])),
+                            }
+                            line
+                        })
                         .collect();
                     let commit_str = commit_lines.join("\n");
-                    ("Selection".to_string(), commit_str)
+                    ("Selection".to_string(), commit_str, selection)
                 }
                 crate::CommitTab::Stats => {
-                    ("Stats".to_string(), String::new())
+                    ("Stats".to_string(), String::new(), vec![])
                 }
             };
-            let prompt = if let Some(path) = prompt_path {
-                match std::fs::read_to_string(path) {
-                    Ok(mut template) => {
-                        template = template.replace("{from}", &from_date);
-                        template = template.replace("{to}", &to_date);
-                        template = template.replace("{project}", &project_name);
-                        template = template.replace("{projectname}", &project_name);
-                        template = template.replace("{interval}", interval_str);
-                        template = template.replace("{lang}", lang);
-                        template = template.replace("{commits}", &commit_str);
-                        template
-                    }
-                    Err(e) => {
-                        eprintln!("Error loading custom prompt '{}': {}. Falling back to default prompt.", path, e);
-                        crate::prompts::prompt_en(&from_date, &to_date, &project_name, lang, &commit_str)
-                    }
+            // Only tickets that are already cached; lookups run in the background
+            let (ticket_details, tickets) = match ticket_projects {
+                Some(projects) => {
+                    let keys = tickets_in(summary_commits, projects);
+                    let cache = ticket_cache.lock().unwrap();
+                    (cache.details(&keys), cache.infos(&keys))
                 }
-            } else {
-                crate::prompts::prompt_en(&from_date, &to_date, &project_name, lang, &commit_str)
+                None => (String::new(), vec![]),
+            };
+            let forge_activity = match selected_tab {
+                crate::CommitTab::Timeframe if *selected_repo_index == usize::MAX => forge_activity.lock().unwrap().for_prompt(None),
+                crate::CommitTab::Timeframe => forge_activity.lock().unwrap().for_prompt(Some(&project_name)),
+                _ => String::new(),
+            };
+            let notes = DayNotes::load(time_range.zone.today());
+            let vars = PromptVars {
+                from: &from_date,
+                to: &to_date,
+                project: &project_name,
+                interval: &interval_str,
+                lang,
+                commits: &commit_str,
+                authors: &author_names,
+                ticket_details: &ticket_details,
+                forge_activity: &forge_activity,
+                plans: &notes.plans,
+                blockers: &notes.blockers,
+                commit_data: &prompt_commits,
+                filter_by_user: *filter_by_user,
+                tickets: &tickets,
             };
-            {
+            let repo_names: Vec<String> = match selected_tab {
+                crate::CommitTab::Selection => prompt_commits.iter().map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string()).collect(),
+                _ => match summary_repo {
+                    Some((repo, _)) if *selected_repo_index != usize::MAX => vec![repo.file_name().unwrap_or_default().to_string_lossy().to_string()],
+                    _ => summary_commits.iter().map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string()).collect(),
+                },
+            };
+            // Also shown if the request fails
+            let fallback = offline_summary(&vars);
+            if provider.effective() == Provider::Offline {
                 let mut p = popup_quote.lock().unwrap();
                 p.visible = true;
-                p.loading = true;
-                p.spinner_frame = 0;
-                p.text = match (&debug_msg, prompt_path) {
-                    (Some(msg), Some(_)) | (Some(msg), None) => format!(
-                        "{msg}\n\nPrompt variables:\n----------------\nfrom: {from}\nto: {to}\nproject: {project}\nlang: {lang}\ngemini_model: {gemini_model}\ncommits: [length: {} chars]\n\nLoading commit summary...",
-                        commit_str.len(),
-                        msg=msg,
-                        from=from_date,
-                        to=to_date,
-                        project=project_name,
-                        lang=lang,
-                        gemini_model=gemini_model
-                    ),
-                    (None, _) => format!(
-                        "Prompt variables:\n----------------\nfrom: {from}\nto: {to}\nproject: {project}\nlang: {lang}\ngemini_model: {gemini_model}\ncommits: [length: {} chars]\n\nLoading commit summary...",
-                        commit_str.len(),
-                        from=from_date,
-                        to=to_date,
-                        project=project_name,
-                        lang=lang,
-                        gemini_model=gemini_model
-                    ),
-                };
+                p.loading = false;
+                p.scroll = 0;
+                p.cached = false;
+                p.start_conversation(None);
+                p.offline = true;
+                p.status = (provider == Provider::Gemini).then(|| "No Gemini API key found, showing the offline summary".to_string());
+                p.text = fallback.clone();
+                HistoryEntry::new("summary", &project_name, time_range, repo_names, "offline", lang, None).archive(&fallback);
+                return Ok(true);
             }
-            // Check for Gemini API key before spawning async task
-            if std::env::var("GEMINI_API_KEY").is_err() {
-                let config_path = crate::config::get_user_config_path();
-                let error_message = format!(
-                    "Gemini API key not found.\n\nPlease add it to your configuration file at:\n{}\n\nOr set it as an environment variable: export GEMINI_API_KEY=your-key",
-                    config_path.display()
+            // Sensitive data is masked before the prompt is rendered, shown or sent
+            let redacted = redactor.redact_vars(&vars, &repo_names);
+            let masked = redacted.masked.summary();
+            let prompt = match preset.build_prompt(&redacted.vars(&vars)) {
+                Ok(prompt) => prompt,
+                Err(e) => {
+                    // Nothing is sent; the popup shows where the template is broken
+                    let mut p = popup_quote.lock().unwrap();
+                    p.visible = true;
+                    p.loading = false;
+                    p.scroll = 0;
+                    p.start_conversation(None);
+                    p.text = e.to_string();
+                    return Ok(true);
+                }
+            };
+            // Part of the prompt so the preview shows it
+            let prompt = if structured_summary { prompt + structured::INSTRUCTIONS } else { prompt };
+            let details = format!(
+                "{msg}\n\nPrompt variables:\n----------------\nfrom: {from}\nto: {to}\nproject: {project}\nlang: {lang}\ngemini_model: {gemini_model}\ncommits: [length: {} chars]\nmasked: {masked}\n\nLoading commit summary...",
+                commit_str.len(),
+                msg=preset_msg,
+                from=from_date,
+                to=to_date,
+                project=project_name,
+                lang=lang,
+                gemini_model=gemini_model,
+                masked=masked.as_deref().unwrap_or("nothing")
+            );
+            let entry = HistoryEntry::new("summary", &project_name, time_range, repo_names, gemini_model, lang, Some(&prompt));
+            if preview_prompt && !refresh {
+                let mut p = popup_quote.lock().unwrap();
+                p.visible = true;
+                p.loading = false;
+                p.status = None;
+                p.editor = None;
+                p.start_conversation(None);
+                let mut editor = TextEditor::new(&prompt);
+                // Start at the top of the prompt
+                editor.row = 0;
+                editor.col = 0;
+                p.preview = Some(PromptPreview {
+                    editor,
+                    editing: false,
+                    details,
+                    masked,
+                    fallback,
+                    lang: lang.to_string(),
+                    model: gemini_model.to_string(),
+                    refresh,
+                    entry,
+                });
+                return Ok(true);
+            }
+            send_summary(rt, popup_quote, prompt, details, fallback, lang.to_string(), gemini_model.to_string(), cache_hours, refresh, structured_summary, entry);
+        }
+        KeyCode::Char('T') => {
+            let mut p = popup_quote.lock().unwrap();
+            p.visible = true;
+            p.scroll = 0;
+            p.spinner_frame = 0;
+            p.status = None;
+            p.start_conversation(None);
+            if team.is_empty() {
+                p.loading = false;
+                p.text = format!(
+                    "No team configured.\n\nAdd [[team]] entries with a name and emails to:\n{}",
+                    crate::config::get_user_config_path().display()
                 );
-                popup_quote.lock().unwrap().text = error_message;
                 return Ok(true);
             }
-            let p2 = popup_quote.clone();
-            let lang_owned = lang.to_string();
-            let gemini_model = gemini_model.to_string();
-            rt.spawn(async move {
-                // Animate spinner while loading
-                let popup_clone = p2.clone();
-                let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
-                // Start spinner loop and summary fetch in parallel
-                let fetch = crate::network::fetch_gemini_commit_summary(&prompt, &lang_owned, &gemini_model);
-                tokio::pin!(fetch);
-                loop {
-                    tokio::select! {
-                        _ = interval.tick() => {
-                            let mut p = popup_clone.lock().unwrap();
-                            if !p.loading { break; }
-                            p.spinner_frame = p.spinner_frame.wrapping_add(1);
-                        }
-                        result = &mut fetch => {
-                            let summary = match result {
-                                Ok(s) => s,
-                                Err(e) => format!("Gemini error: {}", e),
-                            };
-                            let mut p = popup_clone.lock().unwrap();
-                            p.text = summary;
-                            p.loading = false;
-                            break;
-                        }
+            let offline = provider.effective() == Provider::Offline;
+            p.loading = true;
+            p.offline = offline;
+            p.cached = false;
+            p.text = format!("Generating team report for {} members ({})...", team.len(), time_range.label);
+            drop(p);
+            let report = TeamReport {
+                mode: team_mode,
+                from: time_range.start_date(),
+                to: time_range.end_date(),
+                interval: time_range.label.clone(),
+                lang: lang.to_string(),
+                model: gemini_model.to_string(),
+                prompt_path: prompt_path.map(str::to_string),
+                cache_hours,
+                refresh: false,
+                redactor: redactor.clone(),
+                offline,
+            };
+            let repo_names = repos.iter().map(|r| r.file_name().unwrap_or_default().to_string_lossy().to_string()).collect();
+            let entry = HistoryEntry::new("team", "Team", time_range, repo_names, report.provider_model(), lang, None);
+            let (repos, range, team) = (repos.clone(), time_range.clone(), team.to_vec());
+            spawn_popup_task(rt, popup_quote, async move {
+                // git runs per member and repo, so keep it off the UI thread
+                let collected = tokio::task::spawn_blocking(move || collect_team_commits(&repos, &range, false, &team)).await;
+                let member_commits = match collected.map_err(anyhow::Error::from).and_then(|r| r) {
+                    Ok(member_commits) => member_commits,
+                    Err(e) => return format!("Failed to collect team commits: {}", e),
+                };
+                let report = report.generate(&member_commits).await;
+                entry.archive(&report);
+                report
+            });
+        }
+        KeyCode::Char('e') if !popup_quote.lock().unwrap().loading => {
+            // Ask for the path first, starting at the configured one
+            export_dialog.path = export_path.to_string();
+            export_dialog.confirm_overwrite = false;
+            export_dialog.visible = true;
+        }
+        KeyCode::Enter => {
+            // Follow-up question about the summary
+            let mut p = popup_quote.lock().unwrap();
+            if p.visible && !p.loading {
+                if p.prompt.is_some() {
+                    p.chat_input = Some(String::new());
+                    p.status = None;
+                } else if p.offline {
+                    p.status = Some("Follow-ups need an AI summary; this one was generated offline".to_string());
+                } else {
+                    p.status = Some("Follow-ups need a summary generated in this session, press a first".to_string());
+                }
+            }
+        }
+        KeyCode::Char('i') | KeyCode::Char('E') => {
+            let mut p = popup_quote.lock().unwrap();
+            if p.visible && !p.loading {
+                if key == KeyCode::Char('i') {
+                    p.editor = Some(TextEditor::new(&p.text));
+                    p.status = None;
+                } else {
+                    p.external_edit = true;
+                }
+            }
+        }
+        KeyCode::Char('J') => {
+            // Like export: the open summary, otherwise the commit list
+            let mut p = popup_quote.lock().unwrap();
+            if p.loading {
+                return Ok(true);
+            }
+            let status = match journal {
+                None => "No journal configured. Add a [journal] table to whid.toml".to_string(),
+                Some(journal) => {
+                    let summary = if p.visible { Some(p.text.clone()) } else { None };
+                    let mut doc = ExportDoc::new(time_range, summary, commits, *filter_by_user, embed_commit_links.then_some(commit_links));
+                    if p.visible {
+                        doc.sections = p.shown_sections().map(|s| s.summary.clone());
+                    }
+                    match write_journal(journal, time_range, &doc) {
+                        Ok(path) => format!("Written to journal {}", path.display()),
+                        Err(e) => format!("Journal failed: {}", e),
                     }
                 }
-            });
+            };
+            p.status = Some(status);
+        }
+        KeyCode::Char('P') => {
+            // Publishing always asks for confirmation first
+            let mut p = popup_quote.lock().unwrap();
+            if p.visible && !p.loading {
+                if publish_targets.is_empty() {
+                    p.status = Some("No publish targets configured. Add [[publish]] entries to whid.toml".to_string());
+                } else {
+                    publish_dialog.cursor = publish_dialog.cursor.min(publish_targets.len() - 1);
+                    publish_dialog.visible = true;
+                }
+            }
+        }
+        KeyCode::Char('o') | KeyCode::Char('y') if *focus == FocusArea::CommitList && *selected_tab == crate::CommitTab::Timeframe => {
+            let Some((repo, line)) = selected_commit_index.and_then(|i| selected_commit(commits, *selected_repo_index, i)) else {
+                return Ok(true);
+            };
+            let status = match commit_links.commit_url(repo, commit_hash(line)) {
+                None => "No web URL known for this repo's origin. Map its host in [commit_links]".to_string(),
+                Some(url) if key == KeyCode::Char('o') => match open_in_browser(&url) {
+                    Ok(()) => format!("Opened {}", url),
+                    Err(e) => format!("Could not open browser: {}", e),
+                },
+                Some(url) => match Clipboard::new().and_then(|mut cb| cb.set_text(url.clone())) {
+                    Ok(()) => format!("Copied {}", url),
+                    Err(e) => format!("Could not copy: {}", e),
+                },
+            };
+            popup_quote.lock().unwrap().status = Some(status);
         }
         KeyCode::Char('c') => {
             // Kopieren, wenn Popup sichtbar
@@ -435,22 +1018,147 @@ pub fn handle_key(
             }
         }
         KeyCode::Esc => { 
-            let mut p = popup_quote.lock().unwrap(); p.visible=false; p.scroll=0; 
+            let mut p = popup_quote.lock().unwrap();
+            if !p.visible && search.is_filtering() {
+                // Esc outside of popups clears the search
+                search.set_query(String::new());
+                *commits = filter_commits(loaded_commits, search, *filter_by_user);
+                *selected_commit_index = None;
+            }
+            p.visible=false; p.scroll=0; p.status=None;
             let mut sel = selected_commits.lock().unwrap(); sel.popup_visible = false;
         }
+        KeyCode::Char('r') => {
+            // Open the date range dialog at the end of the current range
+            range_dialog.cursor = time_range.until.date_naive();
+            range_dialog.from = None;
+            range_dialog.visible = true;
+        },
         KeyCode::Char('d') => {
             *detailed_commit_view = !*detailed_commit_view;
-            *commits = reload_commits(repos, *current_interval, *filter_by_user, *detailed_commit_view, from_date, to_date)?;
+            *time_range = TimeRange::resolve(date_bounds, &intervals[*current_index], time_range.zone);
+            *loaded_commits = reload_commits(repos, time_range, *filter_by_user, *detailed_commit_view, &author_picker.selected)?;
+            *commits = filter_commits(loaded_commits, search, *filter_by_user);
         },
         _ => {}
     }
-    *current_interval = intervals[*current_index].1;
     Ok(true)
 }
 
+/// Handles a key in the history view: browse, search, reopen, copy and diff.
+fn handle_history_key(key: KeyCode, history: &mut HistoryView, popup_quote: &Arc<Mutex<PopupQuote>>) {
+    if history.diff.is_some() {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => history.diff_scroll = history.diff_scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => history.diff_scroll += 1,
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('d') => history.diff = None,
+            _ => {}
+        }
+        return;
+    }
+    if history.searching {
+        match key {
+            KeyCode::Char(c) => history.query.push(c),
+            KeyCode::Backspace => { history.query.pop(); }
+            KeyCode::Enter => history.searching = false,
+            KeyCode::Esc => {
+                history.searching = false;
+                history.query.clear();
+            }
+            _ => {}
+        }
+        history.cursor = 0;
+        return;
+    }
+    let count = history.filtered().len();
+    history.message = None;
+    match key {
+        KeyCode::Up | KeyCode::Char('k') => history.cursor = history.cursor.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') if history.cursor + 1 < count => {
+            history.cursor += 1;
+        }
+        KeyCode::Char('/') => history.searching = true,
+        KeyCode::Enter | KeyCode::Char('o') => {
+            if let Some(entry) = history.selected() {
+                let mut p = popup_quote.lock().unwrap();
+                p.text = entry.text.clone();
+                p.visible = true;
+                p.loading = false;
+                p.scroll = 0;
+                p.editor = None;
+                p.start_conversation(None);
+                p.status = Some(format!("Archived summary from {}", entry.created_at));
+                history.visible = false;
+            }
+        }
+        KeyCode::Char('c') => {
+            if let Some(entry) = history.selected() {
+                let copied = Clipboard::new().and_then(|mut cb| cb.set_text(entry.text.clone()));
+                history.message = Some(match copied {
+                    Ok(()) => "Copied".to_string(),
+                    Err(e) => format!("Could not copy: {}", e),
+                });
+            }
+        }
+        KeyCode::Char('d') => {
+            // First d marks a summary, the second compares it with the selected one
+            let Some(selected) = history.selected().cloned() else { return };
+            let marked = history.marked.as_ref().and_then(|id| history.entries.iter().find(|e| &e.id == id)).cloned();
+            match marked {
+                Some(marked) if marked.id != selected.id => {
+                    let (old, new) = if marked.created_at <= selected.created_at { (marked, selected) } else { (selected, marked) };
+                    history.diff = Some(diff_lines(&old.text, &new.text));
+                    history.diff_scroll = 0;
+                    history.marked = None;
+                    history.message = Some(format!("{} → {}", old.label(), new.label()));
+                }
+                Some(_) => history.marked = None,
+                None => {
+                    history.marked = Some(selected.id.clone());
+                    history.message = Some("Marked; select another summary and press d to compare".to_string());
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => history.visible = false,
+        _ => {}
+    }
+}
+
+/// Handles a key while the date range dialog is open.
+/// Returns the picked bounds once both ends are chosen.
+fn handle_range_dialog_key(key: KeyCode, dialog: &mut DateRangeDialog) -> Option<DateBounds> {
+    match key {
+        KeyCode::Left | KeyCode::Char('h') => dialog.cursor = dialog.cursor - Days::new(1),
+        KeyCode::Right | KeyCode::Char('l') => dialog.cursor = dialog.cursor + Days::new(1),
+        KeyCode::Up | KeyCode::Char('k') => dialog.cursor = dialog.cursor - Days::new(7),
+        KeyCode::Down | KeyCode::Char('j') => dialog.cursor = dialog.cursor + Days::new(7),
+        KeyCode::PageUp => dialog.cursor = dialog.cursor - Months::new(1),
+        KeyCode::PageDown => dialog.cursor = dialog.cursor + Months::new(1),
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            match dialog.from {
+                None => dialog.from = Some(dialog.cursor),
+                Some(from) => {
+                    let (from, to) = if from <= dialog.cursor { (from, dialog.cursor) } else { (dialog.cursor, from) };
+                    dialog.visible = false;
+                    dialog.from = None;
+                    return Some(DateBounds { from: Some(from), to: Some(to) });
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            dialog.visible = false;
+            dialog.from = None;
+        }
+        _ => {}
+    }
+    None
+}
+
+/// Returns whether the AI Summary button was clicked.
+#[allow(clippy::too_many_arguments)]
 pub fn handle_mouse(
     mouse_event: MouseEvent,
-    repos: &Vec<PathBuf>,
+    repos: &[PathBuf],
     commits: &CommitData,
     selected_repo_index: &mut usize,
     selected_commit_index: &mut Option<usize>,
@@ -462,16 +1170,10 @@ pub fn handle_mouse(
     selected_commits: &Arc<Mutex<SelectedCommits>>,
     sidebar_area: ratatui::prelude::Rect,
     selected_tab: &mut crate::CommitTab,
-    lang: &str, // <-- add lang argument
-    prompt_path: Option<&str>, // <-- add prompt_path argument
-    gemini_model: &str, // <-- add gemini_model argument
-) {
-    use std::thread;
-    use tokio::runtime::Runtime;
-    use std::fs;
+) -> bool {
     if let MouseEventKind::Down(_) = mouse_event.kind {
-        let x = mouse_event.column as u16;
-        let y = mouse_event.row as u16;
+        let x = mouse_event.column;
+        let y = mouse_event.row;
         // Check for popup summary X button
         {
             let popup = popup_quote.lock().unwrap();
@@ -489,7 +1191,8 @@ pub fn handle_mouse(
                     drop(popup); // unlock
                     let mut popup = popup_quote.lock().unwrap();
                     popup.visible = false;
-                    return;
+                    popup.preview = None;
+                    return false;
                 }
             }
         }
@@ -502,58 +1205,11 @@ pub fn handle_mouse(
                 let mut sel = selected_commits.lock().unwrap();
                 sel.popup_visible = true;
                 *focus = FocusArea::Sidebar;
-                return;
+                return false;
             } else if y == button_box_start + 2 {
-                // AI Summary button
-                let prompt_template = if let Some(path) = prompt_path {
-                    fs::read_to_string(path).unwrap_or_else(|_| {
-                        String::from("No custom prompt file provided.")
-                    })
-                } else {
-                    String::from("No custom prompt file provided.")
-                };
-                let (project_name, commit_str) = if *selected_repo_index == usize::MAX {
-                    let all_commits = commits.iter()
-                        .flat_map(|(repo, msgs)| {
-                            let repo_name = repo.file_name().unwrap_or_default().to_string_lossy();
-                            msgs.iter().map(move |msg| format!("[{}] {}", repo_name, msg))
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    ("All projects".to_string(), all_commits)
-                } else {
-                    let project = commits.get(*selected_repo_index)
-                        .map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string())
-                        .unwrap_or_else(|| "Project".to_string());
-                    let commitlist = commits.get(*selected_repo_index)
-                        .map(|(_repo, msgs)| msgs.join("\n"))
-                        .unwrap_or_default();
-                    (project, commitlist)
-                };
-                let interval_label = "";
-                let prompt = format!(
-                    "{template}\n\nProject: {project}\nTimeframe: {interval}\nCommits:\n{commits}",
-                    template=prompt_template,
-                    project=project_name,
-                    interval=interval_label,
-                    commits=commit_str
-                );
-                { let mut p = popup_quote.lock().unwrap(); p.visible=true; p.loading=true; p.spinner_frame=0; p.text=format!("Gemini model: {model}\n\nLoading commit summary...", model=gemini_model); }
-                let popup_quote = popup_quote.clone();
-                let lang_owned = lang.to_string();
-                let gemini_model = gemini_model.to_string();
-                thread::spawn(move || {
-                    let rt = Runtime::new().unwrap();
-                    rt.block_on(async move {
-                        let summary = match crate::network::fetch_gemini_commit_summary(&prompt, &lang_owned, &gemini_model).await {
-                            Ok(s) => s,
-                            Err(e) => format!("Gemini error: {}", e),
-                        };
-                        let mut p = popup_quote.lock().unwrap(); p.text=summary; p.loading=false;
-                    });
-                });
+                // AI Summary button: the caller requests the summary like `A`
                 *focus = FocusArea::Sidebar;
-                return;
+                return true;
             }
             // Sidebar repo selection
             let idx = (y as usize).saturating_sub(1 + *sidebar_scroll);
@@ -566,7 +1222,7 @@ pub fn handle_mouse(
             }
             *selected_commit_index = None;
             // Do not change focus here
-            return;
+            return false;
         } else {
             // Commit list area
             *focus = FocusArea::CommitList;
@@ -579,7 +1235,7 @@ pub fn handle_mouse(
                 for (_repo, repo_commits) in commits {
                     if idx == 0 {
                         // header line, do nothing
-                        return;
+                        return false;
                     }
                     idx -= 1;
                     if idx < repo_commits.len() {
@@ -593,23 +1249,21 @@ pub fn handle_mouse(
                         } else {
                             sel.set.insert(hash);
                         }
-                        return;
+                        return false;
                     }
                     offset += repo_commits.len();
                     idx -= repo_commits.len();
                 }
-            } else if let Some((_repo, repo_commits)) = commits.get(*selected_repo_index) {
-                if idx < repo_commits.len() {
-                    *selected_commit_index = Some(idx);
-                    // Mark/unmark on click
-                    let mut sel = selected_commits.lock().unwrap();
-                    let commit = &repo_commits[idx];
-                    let hash = commit.split_whitespace().next().unwrap_or("").to_string();
-                    if sel.set.contains(&hash) {
-                        sel.set.remove(&hash);
-                    } else {
-                        sel.set.insert(hash);
-                    }
+            } else if let Some((_repo, repo_commits)) = commits.get(*selected_repo_index) && idx < repo_commits.len() {
+                *selected_commit_index = Some(idx);
+                // Mark/unmark on click
+                let mut sel = selected_commits.lock().unwrap();
+                let commit = &repo_commits[idx];
+                let hash = commit.split_whitespace().next().unwrap_or("").to_string();
+                if sel.set.contains(&hash) {
+                    sel.set.remove(&hash);
+                } else {
+                    sel.set.insert(hash);
                 }
             }
         }
@@ -652,8 +1306,8 @@ pub fn handle_mouse(
                 .split(vertical_chunks[0])
         };
         let commit_area = columns[1];
-        let x = mouse_event.column as u16;
-        let y = mouse_event.row as u16;
+        let x = mouse_event.column;
+        let y = mouse_event.row;
         // Only handle click if inside commit list area
         if x >= commit_area.x && x < commit_area.x + commit_area.width && y >= commit_area.y + 3 && y < commit_area.y + commit_area.height {
             // y - (commit_area.y + 3) is the index in the visible list
@@ -666,7 +1320,7 @@ pub fn handle_mouse(
                     if *selected_repo_index == usize::MAX {
                         // All projects: flatten
                         for (_repo, repo_commits) in commits.iter() {
-                            for (_i, _commit) in repo_commits.iter().enumerate() {
+                            for _commit in repo_commits.iter() {
                                 if offset == list_index + *commitlist_scroll {
                                     found = Some(offset);
                                     break;
@@ -675,10 +1329,8 @@ pub fn handle_mouse(
                             }
                             if found.is_some() { break; }
                         }
-                    } else if let Some((_repo, repo_commits)) = commits.get(*selected_repo_index) {
-                        if list_index + *commitlist_scroll < repo_commits.len() {
-                            found = Some(list_index + *commitlist_scroll);
-                        }
+                    } else if let Some((_repo, repo_commits)) = commits.get(*selected_repo_index) && list_index + *commitlist_scroll < repo_commits.len() {
+                        found = Some(list_index + *commitlist_scroll);
                     }
                     if let Some(idx) = found {
                         *selected_commit_index = Some(idx);
@@ -697,7 +1349,7 @@ pub fn handle_mouse(
                     // Kein Commit auswählbar im Stats-Tab
                 }
             }
-            return;
+            return false;
         }
     }
     if let MouseEventKind::ScrollUp = mouse_event.kind {
@@ -706,15 +1358,14 @@ pub fn handle_mouse(
             let area = ratatui::prelude::Rect { x: 0, y: 0, width: area.0, height: area.1 };
             crate::ui::centered_rect(60, 80, area)
         };
-        let x = mouse_event.column as u16;
-        let y = mouse_event.row as u16;
-        if let Ok(mut popup) = popup_quote.lock() {
-            if popup.visible && x >= popup_area.x && x < popup_area.x + popup_area.width && y >= popup_area.y && y < popup_area.y + popup_area.height {
-                if popup.scroll > 0 {
-                    popup.scroll -= 1;
-                }
-                return;
+        let x = mouse_event.column;
+        let y = mouse_event.row;
+        if let Ok(mut popup) = popup_quote.lock()
+            && popup.visible && x >= popup_area.x && x < popup_area.x + popup_area.width && y >= popup_area.y && y < popup_area.y + popup_area.height {
+            if popup.scroll > 0 {
+                popup.scroll -= 1;
             }
+            return false;
         }
     }
     if let MouseEventKind::ScrollDown = mouse_event.kind {
@@ -723,17 +1374,142 @@ pub fn handle_mouse(
             let area = ratatui::prelude::Rect { x: 0, y: 0, width: area.0, height: area.1 };
             crate::ui::centered_rect(60, 80, area)
         };
-        let x = mouse_event.column as u16;
-        let y = mouse_event.row as u16;
-        if let Ok(mut popup) = popup_quote.lock() {
-            if popup.visible && x >= popup_area.x && x < popup_area.x + popup_area.width && y >= popup_area.y && y < popup_area.y + popup_area.height {
-                let text_lines = popup.text.lines().count() as u16;
-                let popup_height = popup_area.height.saturating_sub(4); // account for padding/title/footer
-                if popup.scroll + popup_height < text_lines {
-                    popup.scroll += 1;
+        let x = mouse_event.column;
+        let y = mouse_event.row;
+        if let Ok(mut popup) = popup_quote.lock()
+            && popup.visible && x >= popup_area.x && x < popup_area.x + popup_area.width && y >= popup_area.y && y < popup_area.y + popup_area.height {
+            let text_lines = popup.text.lines().count() as u16;
+            let popup_height = popup_area.height.saturating_sub(4); // account for padding/title/footer
+            if popup.scroll + popup_height < text_lines {
+                popup.scroll += 1;
+            }
+        }
+    }
+    false
+}
+/// Applies an editing key to `editor`; Esc is left to the caller.
+fn edit_text(editor: &mut TextEditor, key: KeyCode) {
+    match key {
+        KeyCode::Char(c) => editor.insert(c),
+        KeyCode::Enter => editor.newline(),
+        KeyCode::Backspace => editor.backspace(),
+        KeyCode::Delete => editor.delete(),
+        KeyCode::Left => editor.left(),
+        KeyCode::Right => editor.right(),
+        KeyCode::Up => editor.up(),
+        KeyCode::Down => editor.down(),
+        KeyCode::Home => editor.home(),
+        KeyCode::End => editor.end(),
+        KeyCode::Tab => (0..4).for_each(|_| editor.insert(' ')),
+        _ => {}
+    }
+}
+
+/// Requests the summary for `prompt` and shows it in the popup, with `details`
+/// while it loads. The prompt is kept for follow-up questions. If the request
+/// fails, the `fallback` offline summary is shown with the error in the footer.
+/// A `structured` response is shown as sections, or as text if it is invalid.
+#[allow(clippy::too_many_arguments)]
+fn send_summary(
+    rt: &Runtime,
+    popup_quote: &Arc<Mutex<PopupQuote>>,
+    prompt: String,
+    details: String,
+    fallback: String,
+    lang: String,
+    model: String,
+    cache_hours: u64,
+    refresh: bool,
+    structured: bool,
+    mut entry: HistoryEntry,
+) {
+    {
+        let mut p = popup_quote.lock().unwrap();
+        p.visible = true;
+        p.loading = true;
+        p.scroll = 0;
+        p.spinner_frame = 0;
+        p.status = None;
+        p.cached = false;
+        p.start_conversation(Some(prompt.clone()));
+        p.text = details;
+    }
+    // The prompt may have been edited in the preview
+    entry.prompt_hash = Some(prompt_hash(&prompt));
+    let popup = popup_quote.clone();
+    spawn_popup_task(rt, popup_quote, async move {
+        let error = match crate::network::fetch_summary_cached(&prompt, &lang, &model, cache_hours, refresh, structured).await {
+            Ok((s, cached)) if cached || !is_error_text(&s) => {
+                let s = if structured { structured_text(&popup, s) } else { s };
+                // A cache hit was archived when it was first generated
+                if cached {
+                    popup.lock().unwrap().cached = true;
+                } else {
+                    entry.archive(&s);
                 }
-                return;
+                return s;
             }
+            Ok((error, _)) => error,
+            Err(e) => format!("Gemini error: {}", e),
+        };
+        let mut p = popup.lock().unwrap();
+        // Follow-ups would go to the failing provider as well
+        p.prompt = None;
+        p.offline = true;
+        p.status = Some(format!("{} – showing the offline summary", error.lines().next().unwrap_or_default()));
+        fallback
+    });
+}
+
+/// Validates a structured response and sets up its sections; the Markdown
+/// rendering becomes the summary text. Invalid responses are shown as they are.
+fn structured_text(popup: &Arc<Mutex<PopupQuote>>, response: String) -> String {
+    let mut p = popup.lock().unwrap();
+    match structured::parse(&response) {
+        Ok(summary) => {
+            let text = summary.to_markdown();
+            p.sections = Some(SummarySections::new(summary));
+            text
         }
+        Err(e) => {
+            p.status = Some(format!("Structured summary invalid ({}) – showing the text", e));
+            response
+        }
+    }
+}
+
+/// Directory name of the repo a summary is requested for, if it is a single one.
+fn summary_repo_name(commits: &CommitData, selected_repo_index: usize, selected_tab: crate::CommitTab) -> Option<String> {
+    if selected_tab != crate::CommitTab::Timeframe {
+        return None;
     }
-}
\ No newline at end of file
+    commits.get(selected_repo_index).map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string())
+}
+
+/// Runs `task` in the background, animating the popup spinner until it finishes
+/// and then showing its result.
+fn spawn_popup_task<F>(rt: &Runtime, popup_quote: &Arc<Mutex<PopupQuote>>, task: F)
+where
+    F: Future<Output = String> + Send + 'static,
+{
+    let popup_clone = popup_quote.clone();
+    rt.spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
+        tokio::pin!(task);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let mut p = popup_clone.lock().unwrap();
+                    if !p.loading { break; }
+                    p.spinner_frame = p.spinner_frame.wrapping_add(1);
+                }
+                text = &mut task => {
+                    let mut p = popup_clone.lock().unwrap();
+                    p.text = text;
+                    p.loading = false;
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use crate::config::JournalConfig;
+use crate::export::ExportDoc;
+use crate::range::TimeRange;
+
+/// Expands `~` and the date placeholders `{YYYY-MM-DD}` (or `{date}`),
+/// `{YYYY}`, `{MM}` and `{DD}`. The date is the last day of the range, so a
+/// summary of today lands in today's note.
+pub fn journal_path(pattern: &str, range: &TimeRange) -> PathBuf {
+    let date = range.end_date();
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let path = pattern
+        .replace("{YYYY-MM-DD}", &date)
+        .replace("{date}", &date)
+        .replace("{YYYY}", year)
+        .replace("{MM}", month)
+        .replace("{DD}", day);
+    PathBuf::from(shellexpand::tilde(&path).into_owned())
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (hashes > 0 && hashes <= 6 && line[hashes..].starts_with(' ')).then_some(hashes)
+}
+
+/// The heading level of each line; lines inside code fences are never headings.
+fn heading_levels(lines: &[&str]) -> Vec<Option<usize>> {
+    let mut in_code_block = false;
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return None;
+            }
+            heading_level(line).filter(|_| !in_code_block)
+        })
+        .collect()
+}
+
+/// The level of the `[journal] heading`. Anything but a Markdown heading is
+/// rejected: without a level there is no telling where the section ends.
+pub fn section_level(heading: &str) -> Result<usize> {
+    heading_level(heading.trim())
+        .ok_or_else(|| anyhow!("The journal heading '{}' must be a Markdown heading, e.g. \"## Standup\"", heading.trim()))
+}
+
+/// Shifts the headings in `content` so the top one sits just below `level`;
+/// that way the section ends at the next heading of the note, not inside the summary.
+fn demote_headings(content: &str, level: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let levels = heading_levels(&lines);
+    let top = levels.iter().flatten().min().copied().unwrap_or(level + 1);
+    let shift = (level + 1).saturating_sub(top);
+    lines
+        .iter()
+        .zip(levels)
+        .map(|(line, own)| match own {
+            Some(own) if shift > 0 => format!("{}{}", "#".repeat((own + shift).min(6)), &line[own..]),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Puts `content` under `heading` in `note`. An existing section with that
+/// heading is replaced up to the next heading of the same or a higher level;
+/// otherwise the section is appended. Headings in code fences are ignored.
+pub fn upsert_section(note: &str, heading: &str, content: &str) -> Result<String> {
+    let heading = heading.trim();
+    let level = section_level(heading)?;
+    let section = format!("{}\n\n{}\n", heading, demote_headings(content.trim(), level));
+    let lines: Vec<&str> = note.lines().collect();
+    let levels = heading_levels(&lines);
+    let Some(start) = (0..lines.len()).find(|&i| levels[i] == Some(level) && lines[i].trim_end() == heading) else {
+        let note = note.trim_end();
+        return Ok(if note.is_empty() { section } else { format!("{}\n\n{}", note, section) });
+    };
+    let end = (start + 1..lines.len())
+        .find(|&i| levels[i].is_some_and(|own| own <= level))
+        .unwrap_or(lines.len());
+    let mut out = lines[..start].join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&section);
+    if end < lines.len() {
+        out.push('\n');
+        out.push_str(&lines[end..].join("\n"));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// The summary, or the commit list when there is none.
+pub fn journal_content(doc: &ExportDoc) -> String {
+    match doc.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(summary) => summary.trim().to_string(),
+        None => doc.commits_markdown().trim().to_string(),
+    }
+}
+
+/// Writes the content of `doc` into the day's note and returns its path.
+pub fn write_journal(config: &JournalConfig, range: &TimeRange, doc: &ExportDoc) -> Result<PathBuf> {
+    let content = journal_content(doc);
+    if content.trim().is_empty() {
+        return Err(anyhow!("Nothing to add to the journal"));
+    }
+    let path = journal_path(&config.path, range);
+    let note = match fs::read_to_string(&path) {
+        Ok(note) => note,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("Could not read {}: {}", path.display(), e)),
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, upsert_section(&note, &config.heading, &content)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_to_a_note_without_the_section() {
+        let note = "# Monday\n\nSome thoughts.\n";
+        assert_eq!(
+            upsert_section(note, "## Standup", "- did things").unwrap(),
+            "# Monday\n\nSome thoughts.\n\n## Standup\n\n- did things\n"
+        );
+        assert_eq!(upsert_section("", "## Standup", "- did things").unwrap(), "## Standup\n\n- did things\n");
+    }
+
+    #[test]
+    fn replaces_the_section_up_to_the_next_heading_of_its_level() {
+        let note = "# Monday\n\n## Standup\n\n- old\n\n### Details\n\nold details\n\n## Meetings\n\n- 1:1\n";
+        assert_eq!(
+            upsert_section(note, "## Standup", "- new").unwrap(),
+            "# Monday\n\n## Standup\n\n- new\n\n## Meetings\n\n- 1:1\n"
+        );
+    }
+
+    #[test]
+    fn ends_the_section_at_a_higher_level_heading() {
+        let note = "## Standup\n\n- old\n# Tuesday\n\ntext\n";
+        assert_eq!(upsert_section(note, "## Standup", "- new").unwrap(), "## Standup\n\n- new\n\n# Tuesday\n\ntext\n");
+    }
+
+    #[test]
+    fn demotes_headings_of_the_content_below_the_section() {
+        let note = "## Standup\n\n- old\n\n## Meetings\n";
+        let updated = upsert_section(note, "## Standup", "## alpha\n\n- one\n\n### Daily\n\n- two").unwrap();
+        assert_eq!(updated, "## Standup\n\n### alpha\n\n- one\n\n#### Daily\n\n- two\n\n## Meetings\n");
+        // Running again replaces the section instead of growing it
+        assert_eq!(upsert_section(&updated, "## Standup", "## alpha\n\n- one\n\n### Daily\n\n- two").unwrap(), updated);
+    }
+
+    #[test]
+    fn ignores_headings_in_code_fences() {
+        let note = "```\n## Standup\n```\n\n## Standup\n\n- old\n```sh\n## not a heading\n```\n\n## Meetings\n";
+        assert_eq!(
+            upsert_section(note, "## Standup", "- new").unwrap(),
+            "```\n## Standup\n```\n\n## Standup\n\n- new\n\n## Meetings\n"
+        );
+    }
+
+    #[test]
+    fn rejects_a_heading_without_level() {
+        assert!(upsert_section("Standup\n\nmy notes\n", "Standup", "- new").is_err());
+        assert!(section_level("Standup").is_err());
+        assert!(section_level("#Standup").is_err());
+        assert_eq!(section_level(" ### Standup ").unwrap(), 3);
+    }
+}
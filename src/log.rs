@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+use anyhow::Result;
+use serde::Serialize;
+use crate::git::{get_commit_records, CommitRecord, CommitStats};
+use crate::range::TimeRange;
+use crate::utils::extract_tickets;
+
+/// Version of the `whid log --json` output. Bump on any breaking change;
+/// adding fields is not breaking.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct LogOutput {
+    pub schema_version: u32,
+    pub range: LogRange,
+    pub filters: LogFilters,
+    pub repos: Vec<LogRepo>,
+}
+
+/// The resolved time window, as used for `git log`.
+#[derive(Debug, Serialize)]
+pub struct LogRange {
+    pub label: String,
+    /// ISO 8601 timestamps with offset.
+    pub since: String,
+    pub until: String,
+    /// First and last calendar day (YYYY-MM-DD) in `timezone`.
+    pub from: String,
+    pub to: String,
+    /// IANA name, or "local".
+    pub timezone: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogFilters {
+    pub only_mine: bool,
+    pub authors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogRepo {
+    pub name: String,
+    pub path: String,
+    pub commits: Vec<LogCommit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: LogAuthor,
+    pub date: String,
+    pub subject: String,
+    pub body: String,
+    pub tickets: Vec<String>,
+    pub stats: CommitStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+impl From<CommitRecord> for LogCommit {
+    fn from(record: CommitRecord) -> Self {
+        let tickets = extract_tickets(&format!("{}\n{}", record.subject, record.body));
+        LogCommit {
+            hash: record.hash,
+            short_hash: record.short_hash,
+            author: LogAuthor { name: record.author_name, email: record.author_email },
+            date: record.date,
+            subject: record.subject,
+            body: record.body,
+            tickets,
+            stats: record.stats,
+        }
+    }
+}
+
+/// Collects the log for all repos. Like the sidebar, repos without commits are left out.
+pub fn collect_log(repos: &[PathBuf], range: &TimeRange, only_mine: bool, authors: &[String]) -> Result<LogOutput> {
+    let mut out_repos = vec![];
+    for repo in repos {
+        let records = get_commit_records(repo, range, only_mine, authors)?;
+        if records.is_empty() {
+            continue;
+        }
+        let path = repo.canonicalize().unwrap_or_else(|_| repo.clone());
+        out_repos.push(LogRepo {
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            path: path.display().to_string(),
+            commits: records.into_iter().map(LogCommit::from).collect(),
+        });
+    }
+    Ok(LogOutput {
+        schema_version: SCHEMA_VERSION,
+        range: LogRange {
+            label: range.label.clone(),
+            since: range.git_since(),
+            until: range.git_until(),
+            from: range.start_date(),
+            to: range.end_date(),
+            timezone: range.zone.tz_name().unwrap_or("local").to_string(),
+        },
+        filters: LogFilters { only_mine, authors: authors.to_vec() },
+        repos: out_repos,
+    })
+}
@@ -8,13 +8,32 @@ mod input;
 mod prompts;
 mod config;
 mod theme;
+mod range;
+mod team;
+mod headless;
+mod export;
+mod log;
+mod publish;
+mod tickets;
+mod remote;
+mod forge;
+mod journal;
+mod notes;
+mod editor;
+mod markdown;
+mod history;
+mod cache;
+mod presets;
+mod offline;
+mod redact;
+mod structured;
 
 use std::{env, time::Duration};
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
 use crossterm::{execute, terminal::{self, Clear as CrosstermClear, ClearType, enable_raw_mode, disable_raw_mode}, event::{self, Event, KeyCode, read}, style::Stylize};
 use ratatui::prelude::*;
-use models::{FocusArea, PopupQuote};
+use models::{AuthorPicker, DateRangeDialog, ExportDialog, FocusArea, HistoryView, NotesEditor, PopupQuote, PresetPicker, PublishDialog, SearchState};
 use git::{find_git_repos, reload_commits};
 use ui::render_commits;
 use crate::input::{handle_key, handle_mouse};
@@ -24,35 +43,191 @@ use utils::CommitData;
 use crate::config::Settings;
 use std::io::{self, Write};
 use crate::theme::Theme;
-use clap::Parser;
+use crate::range::{load_intervals, DateBounds, TimeRange, Zone};
+use crate::team::{check_members, TeamReportMode};
+use crate::headless::HeadlessContext;
+use crate::export::ExportFormat;
+use crate::forge::{fetch_activity, forge_remotes, range_key, ForgeActivity};
+use crate::network::Provider;
+use crate::presets::{find, load_presets, PresetDefaults};
+use crate::redact::Redactor;
+use crate::remote::CommitLinker;
+use crate::tickets::{refresh, tickets_in, tracker_from_config, IssueTracker, TicketCache};
+use clap::{Parser, Subcommand};
 
 /// A terminal tool to summarize your Git commit history for daily standups, using AI.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// Time frame to load commits from: today (24h), yesterday (48h), 72h, week, month
+    /// Time frame to load commits from: today (24h), yesterday (48h), 72h, week, month, or an interval label from whid.toml
     #[arg(default_value = "today")]
     timeframe: String,
 
     /// The language for the AI summary
-    #[arg(long)]
+    #[arg(long, global = true)]
     lang: Option<String>,
 
     /// Path to a custom prompt template file
-    #[arg(long)]
+    #[arg(long, global = true)]
     prompt: Option<String>,
 
+    /// Prompt preset: standup, weekly, release-notes, brag, manager or a file in ~/.config/whid/presets
+    #[arg(long, global = true)]
+    preset: Option<String>,
+
+    /// Where summaries come from: gemini or offline
+    #[arg(long, global = true)]
+    provider: Option<String>,
+
     /// The Gemini model to use for summaries (e.g., gemini-1.5-flash)
-    #[arg(long)]
+    #[arg(long, global = true)]
     model: Option<String>,
 
     /// Start date for the commit history (YYYY-MM-DD)
-    #[arg(long, value_name = "YYYY-MM-DD")]
+    #[arg(long, value_name = "YYYY-MM-DD", global = true)]
     from: Option<String>,
 
     /// End date for the commit history (YYYY-MM-DD), defaults to today
-    #[arg(long, value_name = "YYYY-MM-DD")]
+    #[arg(long, value_name = "YYYY-MM-DD", global = true)]
     to: Option<String>,
+
+    /// Ask the AI again instead of reusing a cached response
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Headless commands that print to stdout instead of starting the TUI.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print an AI summary of the commit history
+    Summary {
+        /// Time frame, as for the TUI (defaults to "today")
+        timeframe: Option<String>,
+
+        /// Include everyone's commits, not only your own
+        #[arg(long)]
+        all: bool,
+
+        /// Generate a team report for the [[team]] members in whid.toml
+        #[arg(long)]
+        team: bool,
+
+        /// How the team report is generated: combined or per_person
+        #[arg(long, value_name = "MODE")]
+        team_report: Option<String>,
+
+        /// Publish the summary to a [[publish]] target from whid.toml, or to all of them
+        #[arg(long, value_name = "TARGET", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+        publish: Option<String>,
+
+        /// Publish without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Write the commit list, and optionally an AI summary, to a file
+    Export {
+        /// Time frame, as for the TUI (defaults to "today")
+        timeframe: Option<String>,
+
+        /// Output path; supports ~ and {date}, {from}, {to}, {interval} (defaults to export_path)
+        #[arg(long, short)]
+        output: Option<String>,
+
+        /// md, json, csv, html or txt (defaults to the file extension)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Include everyone's commits, not only your own
+        #[arg(long)]
+        all: bool,
+
+        /// Generate an AI summary and include it
+        #[arg(long)]
+        summary: bool,
+
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the commits of the range, for scripts and dashboards
+    Log {
+        /// Time frame, as for the TUI (defaults to "today")
+        timeframe: Option<String>,
+
+        /// Print a versioned JSON document instead of one line per commit
+        #[arg(long)]
+        json: bool,
+
+        /// Include everyone's commits, not only your own
+        #[arg(long)]
+        all: bool,
+
+        /// Only commits by this author email (repeatable, implies --all)
+        #[arg(long, value_name = "EMAIL")]
+        author: Vec<String>,
+    },
+    /// Write the summary into the daily note
+    Journal {
+        /// Time frame, as for the TUI (defaults to "today")
+        timeframe: Option<String>,
+
+        /// Include everyone's commits, not only your own
+        #[arg(long)]
+        all: bool,
+
+        /// Write the commit list even if an API key is configured
+        #[arg(long)]
+        commits: bool,
+    },
+}
+
+impl Command {
+    fn timeframe(&self) -> Option<&str> {
+        match self {
+            Command::Summary { timeframe, .. }
+            | Command::Export { timeframe, .. }
+            | Command::Log { timeframe, .. }
+            | Command::Journal { timeframe, .. } => timeframe.as_deref(),
+        }
+    }
+}
+
+/// Hands the terminal to `$EDITOR` for the summary text and redraws afterwards.
+fn edit_summary_externally<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, popup_quote: &Arc<Mutex<PopupQuote>>) -> anyhow::Result<()> {
+    // The prompt under review, otherwise the summary
+    let text = {
+        let mut p = popup_quote.lock().unwrap();
+        p.external_edit = false;
+        match &p.preview {
+            Some(preview) => preview.editor.text(),
+            None => p.text.clone(),
+        }
+    };
+    terminal::disable_raw_mode()?;
+    execute!(std::io::stdout(), crossterm::event::DisableMouseCapture, CrosstermClear(ClearType::All))?;
+    let result = editor::edit_externally(&text);
+    terminal::enable_raw_mode()?;
+    execute!(std::io::stdout(), crossterm::event::EnableMouseCapture)?;
+    terminal.clear()?;
+    let mut p = popup_quote.lock().unwrap();
+    match result {
+        Ok(edited) => match p.preview.as_mut() {
+            Some(preview) => preview.editor = editor::TextEditor::new(&edited),
+            None => {
+                // Edited text no longer matches the sections
+                if edited != p.text {
+                    p.sections = None;
+                }
+                p.text = edited;
+                p.status = Some("Summary updated".to_string());
+            }
+        },
+        Err(e) => p.status = Some(format!("Editing failed: {}", e)),
+    }
+    Ok(())
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -80,19 +255,105 @@ impl CommitTab {
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    let date_bounds = DateBounds::parse(cli.from.as_deref(), cli.to.as_deref())?;
     let mut settings = Settings::new().expect("Failed to load settings");
+    let zone = Zone::parse(&settings.timezone)?;
+    let team_mode = TeamReportMode::parse(&settings.team_report)?;
+    check_members(&settings.team)?;
+    let provider = Provider::parse(cli.provider.as_deref().unwrap_or(&settings.provider))?;
+    let intervals = load_intervals(&settings.intervals)?;
+    let lang = cli.lang.clone().or(settings.lang.clone()).unwrap_or_else(|| "en".to_string());
+    let prompt_path = cli.prompt.clone().or(settings.custom_prompt_path.clone());
+    let gemini_model = cli.model.clone().unwrap_or_else(|| settings.gemini_model.clone());
+    let presets = load_presets(prompt_path.as_deref());
+    let redactor = Redactor::new(settings.redaction.as_ref())?;
+    if let Some(journal) = &settings.journal {
+        journal::section_level(&journal.heading)?;
+    }
+    if let Some(name) = &cli.preset && find(&presets, name).is_none() {
+        let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+        return Err(anyhow::anyhow!("Unknown preset '{}'. Available: {}", name, names.join(", ")));
+    }
+    let preset_defaults = PresetDefaults {
+        // --prompt is explicit, so it wins over configured presets
+        cli: cli.preset.clone().or_else(|| cli.prompt.as_ref().map(|_| "custom".to_string())),
+        by_repo: settings.repo_presets.clone(),
+        default: settings.default_preset.clone(),
+    };
+    let timeframe = cli.command.as_ref().and_then(Command::timeframe).unwrap_or(&cli.timeframe).to_string();
+
+    // Prefer an exact label match, then the legacy timeframe names
+    let mut current_index = intervals.iter().position(|i| i.label.eq_ignore_ascii_case(&timeframe))
+        .or_else(|| intervals.iter().position(|i| i.matches(&timeframe)))
+        .unwrap_or(0);
 
     // Check for API key from config or environment variable
     let api_key_from_env = env::var("GEMINI_API_KEY").ok();
     let mut api_key = settings.gemini_api_key.clone().filter(|k| !k.is_empty()).or(api_key_from_env);
 
-    // If no key is found, prompt the user
-    if api_key.is_none() && settings.prompt_for_api_key {
-        if unsafe { prompt_for_api_key()? } {
-            // Re-load settings to get the new key
-            settings = Settings::new().expect("Failed to reload settings after key entry");
-            api_key = settings.gemini_api_key.clone();
+    // Headless commands never prompt; they use a key that is already configured
+    if let Some(command) = &cli.command {
+        if let Some(key) = &api_key {
+            unsafe {
+                env::set_var("GEMINI_API_KEY", key);
+            }
         }
+        let repos = find_git_repos(".")?;
+        let commit_links = settings.embed_commit_links.then(|| CommitLinker::new(&repos, &settings.commit_links));
+        // A repo's own preset applies when whid runs inside that single repo
+        let single_repo = match repos.as_slice() {
+            [repo] => repo.file_name().map(|n| n.to_string_lossy().to_string()),
+            _ => None,
+        };
+        let preset = preset_defaults.resolve(&presets, single_repo.as_deref()).clone();
+        let ctx = HeadlessContext {
+            repos,
+            time_range: TimeRange::resolve(&date_bounds, &intervals[current_index], zone),
+            lang,
+            model: gemini_model,
+            prompt_path,
+            preset,
+            tracker: settings.tracker.clone(),
+            forge: settings.forge.clone(),
+            commit_links,
+            cache_hours: settings.response_cache_hours,
+            refresh: cli.no_cache,
+            redactor,
+            provider,
+            structured: settings.structured_summary,
+        };
+        return match command {
+            Command::Summary { all, team, team_report, publish, yes, .. } => {
+                let team = if *team {
+                    let mode = match team_report {
+                        Some(name) => TeamReportMode::parse(name)?,
+                        None => team_mode,
+                    };
+                    Some((settings.team.as_slice(), mode))
+                } else {
+                    None
+                };
+                let targets = match publish {
+                    Some(name) => headless::publish_targets(&settings.publish, name)?,
+                    None => vec![],
+                };
+                headless::run_summary(&ctx, *all, team, &targets, *yes)
+            }
+            Command::Export { output, format, all, summary, force, .. } => {
+                let format = format.as_deref().map(ExportFormat::parse).transpose()?;
+                let pattern = output.as_deref().unwrap_or(&settings.export_path);
+                headless::run_export(&ctx, pattern, format, *all, *summary, *force)
+            }
+            Command::Log { json, all, author, .. } => headless::run_log(&ctx, *json, *all, author),
+            Command::Journal { all, commits, .. } => headless::run_journal(&ctx, settings.journal.as_ref(), *all, *commits),
+        };
+    }
+
+    // If no key is found, prompt the user; offline summaries need none
+    if api_key.is_none() && settings.prompt_for_api_key && provider == Provider::Gemini && unsafe { prompt_for_api_key()? } {
+        // Re-load settings to get the new key
+        settings = Settings::new().expect("Failed to reload settings after key entry");
+        api_key = settings.gemini_api_key.clone();
     }
     
     // If a key is available (from config or prompt), set it as an env var for gemini-rs to pick up
@@ -103,39 +364,20 @@ fn main() -> anyhow::Result<()> {
     }
 
     let theme = Theme::default();
-    let hours = match cli.timeframe.as_str() {
-        "24" | "today" => 24,
-        "48" | "yesterday" => 48,
-        "72" => 72,
-        "week" => 24 * 7,
-        "month" => 24 * 30,
-        _ => cli.timeframe.parse::<u64>().unwrap_or(24),
-    };
-    let initial_interval = Duration::from_secs(hours * 3600);
-    let lang = cli.lang.or(settings.lang).unwrap_or_else(|| "en".to_string());
-    let prompt_path = cli.prompt.or(settings.custom_prompt_path);
-    let cli_gemini_model = cli.model;
-    let from_date = cli.from;
-    let to_date = cli.to;
-    let mut gemini_model = settings.gemini_model;
-    if let Some(model) = cli_gemini_model {
-        gemini_model = model;
-    }
-
     let repos = find_git_repos(".")?;
-
-    let intervals = vec![
-        ("24h", Duration::from_secs(24 * 3600)),
-        ("48h", Duration::from_secs(48 * 3600)),
-        ("72h", Duration::from_secs(72 * 3600)),
-        ("1 week", Duration::from_secs(7 * 24 * 3600)),
-        ("1 month", Duration::from_secs(30 * 24 * 3600)),
-    ];
-    let mut current_index = intervals.iter().position(|(_, d)| *d == initial_interval).unwrap_or(0);
-    let mut current_interval = intervals[current_index].1;
+    let mut date_bounds = date_bounds;
+    let mut range_dialog = DateRangeDialog::new(zone.today());
     let mut filter_by_user = true;
     let mut detailed_commit_view = false;
-    let mut commits: CommitData = reload_commits(&repos, current_interval, filter_by_user, detailed_commit_view, from_date.clone(), to_date.clone())?;
+    let mut time_range = TimeRange::resolve(&date_bounds, &intervals[current_index], zone);
+    let mut author_picker = AuthorPicker::default();
+    let mut export_dialog = ExportDialog::default();
+    let mut publish_dialog = PublishDialog::default();
+    let mut preset_picker = PresetPicker::default();
+    let mut loaded_commits: CommitData = reload_commits(&repos, &time_range, filter_by_user, detailed_commit_view, &author_picker.selected)?;
+    // The list shown in the TUI: `loaded_commits` narrowed by the search bar
+    let mut search = SearchState::default();
+    let mut commits: CommitData = loaded_commits.clone();
 
     let mut selected_repo_index = usize::MAX;
     let mut selected_commit_index: Option<usize> = None;
@@ -146,7 +388,7 @@ fn main() -> anyhow::Result<()> {
     let mut commitlist_scroll = 0;
     let mut detail_scroll = 0;
 
-    let popup_quote = Arc::new(Mutex::new(PopupQuote { visible: false, text: String::new(), loading: false, scroll: 0, spinner_frame: 0 }));
+    let popup_quote = Arc::new(Mutex::new(PopupQuote { visible: false, text: String::new(), loading: false, scroll: 0, spinner_frame: 0, status: None, editor: None, external_edit: false, cached: false, offline: false, prompt: None, chat: vec![], question: None, chat_input: None, scroll_to_latest: false, preview: None, sections: None }));
     let selected_commits = Arc::new(Mutex::new(SelectedCommits { set: HashSet::new(), popup_visible: false }));
 
     let rt = Runtime::new()?;
@@ -156,9 +398,51 @@ fn main() -> anyhow::Result<()> {
     execute!(std::io::stdout(), CrosstermClear(ClearType::All))?;
     let poll_timeout = std::time::Duration::from_millis(30);
 
+    // Ticket titles are looked up in the background and shown once cached
+    let ticket_cache = Arc::new(Mutex::new(TicketCache::load()));
+    let tracker: Option<Arc<dyn IssueTracker>> = settings.tracker.as_ref().map(tracker_from_config).transpose()?.map(Arc::from);
+    let mut requested_tickets: HashSet<String> = HashSet::new();
+    // Pull request activity is fetched in the background whenever the range changes
+    let forge_activity = Arc::new(Mutex::new(ForgeActivity::default()));
+    let forge_remotes = settings.forge.as_ref().map(|config| forge_remotes(&repos, config)).unwrap_or_default();
+    let commit_links = CommitLinker::new(&repos, &settings.commit_links);
+    let mut notes_editor = NotesEditor::default();
+    let mut history = HistoryView::default();
+
     let mut last_sidebar_area = None;
     let mut selected_tab = CommitTab::Timeframe;
     loop {
+        if let (Some(tracker), Some(tracker_config)) = (&tracker, &settings.tracker) {
+            let keys: Vec<String> = tickets_in(&loaded_commits, &tracker_config.projects)
+                .into_iter()
+                .filter(|key| requested_tickets.insert(key.clone()))
+                .collect();
+            if !keys.is_empty() {
+                let tracker = tracker.clone();
+                let cache = ticket_cache.clone();
+                let max_age = tracker_config.cache_hours;
+                rt.spawn(async move { refresh(tracker.as_ref(), &cache, &keys, max_age).await });
+            }
+        }
+        if let Some(forge_config) = settings.forge.as_ref().filter(|_| !forge_remotes.is_empty()) {
+            let key = range_key(&time_range);
+            let mut activity = forge_activity.lock().unwrap();
+            if activity.range_key != key {
+                *activity = ForgeActivity { range_key: key.clone(), loading: true, ..Default::default() };
+                let state = forge_activity.clone();
+                let config = forge_config.clone();
+                let remotes = forge_remotes.clone();
+                let range = time_range.clone();
+                rt.spawn(async move {
+                    let (items, errors) = fetch_activity(&config, &remotes, &range).await;
+                    let mut activity = state.lock().unwrap();
+                    // Drop results for a range that is no longer shown
+                    if activity.range_key == key {
+                        *activity = ForgeActivity { range_key: key, loading: false, items, errors };
+                    }
+                });
+            }
+        }
         terminal.draw(|f| {
             // Compute layout to get sidebar_area
             let area = f.area();
@@ -191,9 +475,7 @@ fn main() -> anyhow::Result<()> {
                 &repos,
                 selected_repo_index,
                 &commits,
-                intervals[current_index].0,
-                &from_date,
-                &to_date,
+                &time_range,
                 selected_commit_index,
                 show_details,
                 focus,
@@ -205,45 +487,28 @@ fn main() -> anyhow::Result<()> {
                 Some(&selected_commits),
                 selected_tab,
                 detailed_commit_view,
+                &range_dialog,
+                &search,
+                &author_picker,
+                &export_dialog,
+                &publish_dialog,
+                &settings.publish,
+                &ticket_cache,
+                settings.forge.as_ref().map(|_| &forge_activity),
+                &notes_editor,
+                &history,
+                &preset_picker,
+                &presets,
             );
         })?;
 
         if event::poll(poll_timeout)? {
-            match event::read()? {
-                Event::Key(key_event) => {
-                    let handled = handle_key(
-                        key_event.code,
-                        &intervals,
-                        &mut current_index,
-                        &mut current_interval,
-                        &mut filter_by_user,
-                        &repos,
-                        &mut commits,
-                        &mut selected_repo_index,
-                        &mut selected_commit_index,
-                        &mut show_details,
-                        &mut focus,
-                        &mut sidebar_scroll,
-                        &mut commitlist_scroll,
-                        &mut detail_scroll,
-                        &popup_quote,
-                        &selected_commits,
-                        &rt,
-                        &mut selected_tab,
-                        &lang,
-                        prompt_path.as_deref(),
-                        &gemini_model,
-                        &mut detailed_commit_view,
-                        from_date.clone(),
-                        to_date.clone(),
-                    )?;
-                    if !handled {
-                        break;
-                    }
-                }
+            let key = match event::read()? {
+                Event::Key(key_event) => Some(key_event.code),
                 Event::Mouse(mouse_event) => {
+                    let mut summary_clicked = false;
                     if let Some(sidebar_area) = last_sidebar_area {
-                        handle_mouse(
+                        summary_clicked = handle_mouse(
                             mouse_event,
                             &repos,
                             &commits,
@@ -256,9 +521,6 @@ fn main() -> anyhow::Result<()> {
                             &selected_commits,
                             sidebar_area,
                             &mut selected_tab,
-                            &lang,
-                            prompt_path.as_deref(),
-                            &gemini_model,
                         );
                         // Mouse support for commit list tabs
                         // Calculate tab area (same as in ui.rs)
@@ -293,8 +555,8 @@ fn main() -> anyhow::Result<()> {
                         };
                         use crossterm::event::MouseEventKind;
                         if let MouseEventKind::Down(_) = mouse_event.kind {
-                            let x = mouse_event.column as u16;
-                            let y = mouse_event.row as u16;
+                            let x = mouse_event.column;
+                            let y = mouse_event.row;
                             if y >= tabs_area.y && y < tabs_area.y + tabs_area.height {
                                 // Calculate tab title widths with padding
                                 let tab_titles = ["Timeframe", "Selection"];
@@ -311,8 +573,69 @@ fn main() -> anyhow::Result<()> {
                             }
                         }
                     }
+                    // The AI Summary button requests the summary like `A`
+                    summary_clicked.then_some(KeyCode::Char('A'))
+                }
+                _ => None,
+            };
+            if let Some(key) = key {
+                let handled = handle_key(
+                    key,
+                    &intervals,
+                    &mut current_index,
+                    &mut filter_by_user,
+                    &repos,
+                    &mut loaded_commits,
+                    &mut commits,
+                    &mut selected_repo_index,
+                    &mut selected_commit_index,
+                    &mut show_details,
+                    &mut focus,
+                    &mut sidebar_scroll,
+                    &mut commitlist_scroll,
+                    &mut detail_scroll,
+                    &popup_quote,
+                    &selected_commits,
+                    &rt,
+                    &mut selected_tab,
+                    &lang,
+                    prompt_path.as_deref(),
+                    &gemini_model,
+                    &mut detailed_commit_view,
+                    &mut date_bounds,
+                    &mut time_range,
+                    &mut range_dialog,
+                    &mut search,
+                    &mut author_picker,
+                    &settings.team,
+                    team_mode,
+                    &settings.export_path,
+                    &mut export_dialog,
+                    &settings.publish,
+                    &mut publish_dialog,
+                    &ticket_cache,
+                    settings.tracker.as_ref().map(|t| t.projects.as_slice()),
+                    &forge_activity,
+                    &commit_links,
+                    settings.embed_commit_links,
+                    settings.journal.as_ref(),
+                    &mut notes_editor,
+                    &mut history,
+                    settings.response_cache_hours,
+                    &presets,
+                    &preset_defaults,
+                    &mut preset_picker,
+                    settings.preview_prompt,
+                    &redactor,
+                    provider,
+                    settings.structured_summary,
+                )?;
+                if !handled {
+                    break;
+                }
+                if popup_quote.lock().unwrap().external_edit {
+                    edit_summary_externally(&mut terminal, &popup_quote)?;
                 }
-                _ => {}
             }
         }
     }
@@ -0,0 +1,232 @@
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Parser, Tag, TagEnd};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use crate::theme::Theme;
+
+/// Bullets per nesting level; deeper levels repeat the last one.
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Piece of inline text waiting to be wrapped.
+enum Atom {
+    Text(String, Style),
+    Space,
+    Break,
+}
+
+/// Renders Markdown as styled lines, wrapped to `width` with hanging indents so
+/// nested lists keep their structure. The source text itself is not changed.
+pub fn render_markdown(text: &str, width: u16, theme: &Theme) -> Vec<Line<'static>> {
+    let mut r = Renderer {
+        theme,
+        width: width.max(10) as usize,
+        lines: vec![],
+        atoms: vec![],
+        styles: vec![theme.popup_text],
+        lists: vec![],
+        indents: vec![],
+        marker: None,
+        code_block: false,
+    };
+    for event in Parser::new(text) {
+        r.event(event);
+    }
+    r.flush();
+    while r.lines.last().is_some_and(|l| l.width() == 0) {
+        r.lines.pop();
+    }
+    r.lines
+}
+
+struct Renderer<'t> {
+    theme: &'t Theme,
+    width: usize,
+    lines: Vec<Line<'static>>,
+    atoms: Vec<Atom>,
+    /// Inline styles; the last one applies.
+    styles: Vec<Style>,
+    /// Open lists with the next number for ordered ones.
+    lists: Vec<Option<u64>>,
+    /// Continuation indent of each open list item.
+    indents: Vec<usize>,
+    /// Bullet of the current item, printed before its first line.
+    marker: Option<String>,
+    code_block: bool,
+}
+
+impl Renderer<'_> {
+    fn style(&self) -> Style {
+        *self.styles.last().unwrap_or(&self.theme.popup_text)
+    }
+
+    fn push_style(&mut self, f: impl FnOnce(Style) -> Style) {
+        self.styles.push(f(self.style()));
+    }
+
+    fn text(&mut self, text: &str) {
+        let style = self.style();
+        for (i, word) in text.split(' ').enumerate() {
+            if i > 0 {
+                self.atoms.push(Atom::Space);
+            }
+            if !word.is_empty() {
+                self.atoms.push(Atom::Text(word.to_string(), style));
+            }
+        }
+    }
+
+    fn blank(&mut self) {
+        if self.lines.last().is_some_and(|l| l.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.flush();
+                let style = match level {
+                    HeadingLevel::H1 => self.theme.popup_title.add_modifier(Modifier::UNDERLINED),
+                    HeadingLevel::H2 => self.theme.popup_title,
+                    _ => Style::default().fg(self.theme.text_highlight).add_modifier(Modifier::BOLD),
+                };
+                self.styles.push(style);
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                self.flush();
+                self.styles.pop();
+                self.blank();
+            }
+            Event::Start(Tag::Paragraph) => self.flush(),
+            Event::End(TagEnd::Paragraph) => {
+                self.flush();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            Event::Start(Tag::Item) => {
+                self.flush();
+                let indent = self.indents.last().copied().unwrap_or(0);
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => format!("{} ", BULLETS[depth.min(BULLETS.len() - 1)]),
+                };
+                self.indents.push(indent + marker.chars().count());
+                self.marker = Some(format!("{}{}", " ".repeat(indent), marker));
+            }
+            Event::End(TagEnd::Item) => {
+                self.flush();
+                self.indents.pop();
+            }
+            Event::Start(Tag::Emphasis) => self.push_style(|s| s.add_modifier(Modifier::ITALIC)),
+            Event::Start(Tag::Strong) => self.push_style(|s| s.add_modifier(Modifier::BOLD)),
+            Event::Start(Tag::Strikethrough) => self.push_style(|s| s.add_modifier(Modifier::CROSSED_OUT)),
+            Event::Start(Tag::Link { .. }) => self.push_style(|s| s.add_modifier(Modifier::UNDERLINED)),
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link) => {
+                self.styles.pop();
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                self.flush();
+                self.code_block = true;
+                if let CodeBlockKind::Fenced(lang) = kind && !lang.is_empty() {
+                    self.lines.push(Line::from(Span::styled(lang.to_string(), Style::default().fg(self.theme.text_secondary))));
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                self.code_block = false;
+                self.blank();
+            }
+            Event::Text(text) if self.code_block => {
+                // Code keeps its layout and is not wrapped
+                let indent = " ".repeat(self.indents.last().copied().unwrap_or(0) + 2);
+                for line in text.lines() {
+                    self.lines.push(Line::from(Span::styled(format!("{}{}", indent, line), self.theme.commit_hash)));
+                }
+            }
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => self.atoms.push(Atom::Text(format!("`{}`", code), self.theme.commit_hash)),
+            Event::SoftBreak => self.atoms.push(Atom::Space),
+            Event::HardBreak => self.atoms.push(Atom::Break),
+            Event::Rule => {
+                self.flush();
+                self.blank();
+                self.lines.push(Line::from(Span::styled("─".repeat(self.width), Style::default().fg(self.theme.blurred_border))));
+                self.lines.push(Line::default());
+            }
+            Event::TaskListMarker(done) => self.text(if done { "[x]" } else { "[ ]" }),
+            Event::Html(html) | Event::InlineHtml(html) => self.text(&html),
+            _ => {}
+        }
+    }
+
+    /// Wraps the pending inline text into lines. The first line gets the
+    /// item's bullet, later lines the item's indent.
+    fn flush(&mut self) {
+        let indent = self.indents.last().copied().unwrap_or(0);
+        let atoms = std::mem::take(&mut self.atoms);
+        if atoms.iter().all(|a| !matches!(a, Atom::Text(..))) {
+            return;
+        }
+        let mut prefix = self.marker.take().unwrap_or_else(|| " ".repeat(indent));
+        let mut line: Vec<Span<'static>> = vec![];
+        let mut line_width = 0;
+        let mut word: Vec<Span<'static>> = vec![];
+        let mut word_width = 0;
+        let mut pending_space = false;
+        let mut atoms = atoms.into_iter().peekable();
+        while let Some(atom) = atoms.next() {
+            if let Atom::Text(text, style) = atom {
+                word_width += Span::raw(text.as_str()).width();
+                word.push(Span::styled(text, style));
+                if matches!(atoms.peek(), Some(Atom::Text(..))) {
+                    continue;
+                }
+                let available = self.width.saturating_sub(prefix.chars().count());
+                let space = if pending_space && line_width > 0 { 1 } else { 0 };
+                if line_width > 0 && line_width + space + word_width > available {
+                    self.push_line(&prefix, std::mem::take(&mut line));
+                    prefix = " ".repeat(indent);
+                    line_width = 0;
+                } else if space == 1 {
+                    line.push(Span::raw(" "));
+                    line_width += 1;
+                }
+                line.append(&mut word);
+                line_width += word_width;
+                word_width = 0;
+                pending_space = false;
+            } else if let Atom::Break = atom {
+                self.push_line(&prefix, std::mem::take(&mut line));
+                prefix = " ".repeat(indent);
+                line_width = 0;
+                pending_space = false;
+            } else {
+                pending_space = true;
+            }
+        }
+        if !line.is_empty() {
+            self.push_line(&prefix, line);
+        }
+    }
+
+    fn push_line(&mut self, prefix: &str, mut spans: Vec<Span<'static>>) {
+        if !prefix.is_empty() {
+            spans.insert(0, Span::styled(prefix.to_string(), Style::default().fg(self.theme.text_highlight)));
+        }
+        self.lines.push(Line::from(spans));
+    }
+}
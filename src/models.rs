@@ -1,4 +1,12 @@
+use chrono::NaiveDate;
+use regex::{Regex, RegexBuilder};
 use std::collections::HashSet;
+use crate::git::AuthorCount;
+use crate::notes::DayNotes;
+use crate::editor::TextEditor;
+use crate::history::HistoryEntry;
+use crate::structured::StructuredSummary;
+use similar::ChangeTag;
 
 /// Which UI area is currently focused.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -16,6 +24,177 @@ pub struct PopupQuote {
     pub loading: bool,
     pub scroll: u16, // scroll offset for popup summary
     pub spinner_frame: u8, // frame index for loading spinner
+    pub status: Option<String>, // result of the last export, shown in the footer
+    /// Set while the summary is edited inline; `text` is updated when editing ends.
+    pub editor: Option<TextEditor>,
+    /// Asks the main loop to hand the terminal to `$EDITOR` for the summary.
+    pub external_edit: bool,
+    /// The text was answered from the response cache.
+    pub cached: bool,
+    /// The text is the offline summary, not an AI response.
+    pub offline: bool,
+    /// The prompt behind the summary, sent again as context for follow-ups.
+    pub prompt: Option<String>,
+    /// Earlier exchanges, shown above the latest answer in `text`.
+    pub chat: Vec<ChatTurn>,
+    /// The follow-up question that `text` answers.
+    pub question: Option<String>,
+    /// The follow-up input line while it is open.
+    pub chat_input: Option<String>,
+    /// Asks the renderer to scroll to the latest exchange.
+    pub scroll_to_latest: bool,
+    /// A summary request shown for review before it is sent.
+    pub preview: Option<PromptPreview>,
+    /// Set when `text` was rendered from a structured summary; dropped on edits.
+    pub sections: Option<SummarySections>,
+}
+
+/// A rendered summary prompt waiting to be confirmed.
+#[derive(Debug)]
+pub struct PromptPreview {
+    /// The prompt; the cursor row is the line `d` removes.
+    pub editor: TextEditor,
+    /// Keys type into the prompt instead of acting on it.
+    pub editing: bool,
+    /// Shown in the popup while the summary loads.
+    pub details: String,
+    /// What redaction masked, e.g. "2× email, 1× IP address".
+    pub masked: Option<String>,
+    /// The offline summary, shown if the request fails.
+    pub fallback: String,
+    pub lang: String,
+    pub model: String,
+    pub refresh: bool,
+    pub entry: HistoryEntry,
+}
+
+impl PromptPreview {
+    /// Rough token count of the prompt, at about four characters per token.
+    pub fn tokens(&self) -> usize {
+        self.editor.lines.iter().map(|l| l.chars().count() + 1).sum::<usize>().div_ceil(4)
+    }
+}
+
+/// A foldable section header in a structured summary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SectionId {
+    Project(usize),
+    /// Project and topic index.
+    Topic(usize, usize),
+    /// The daily breakdown of a project.
+    Days(usize),
+}
+
+/// A structured summary shown as foldable sections in the popup.
+#[derive(Debug)]
+pub struct SummarySections {
+    pub summary: StructuredSummary,
+    pub folded: HashSet<SectionId>,
+    /// Index into `visible()`.
+    pub cursor: usize,
+    /// Asks the renderer to scroll the cursor into view.
+    pub follow_cursor: bool,
+}
+
+impl SummarySections {
+    pub fn new(summary: StructuredSummary) -> Self {
+        SummarySections { summary, folded: HashSet::new(), cursor: 0, follow_cursor: true }
+    }
+
+    /// Section headers outside folded projects, in display order.
+    pub fn visible(&self) -> Vec<SectionId> {
+        let mut ids = vec![];
+        for (i, project) in self.summary.projects.iter().enumerate() {
+            ids.push(SectionId::Project(i));
+            if self.folded.contains(&SectionId::Project(i)) {
+                continue;
+            }
+            ids.extend((0..project.topics.len()).map(|j| SectionId::Topic(i, j)));
+            if !project.days.is_empty() {
+                ids.push(SectionId::Days(i));
+            }
+        }
+        ids
+    }
+
+    pub fn up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+        self.follow_cursor = true;
+    }
+
+    pub fn down(&mut self) {
+        if self.cursor + 1 < self.visible().len() {
+            self.cursor += 1;
+        }
+        self.follow_cursor = true;
+    }
+
+    /// Folds or unfolds the section under the cursor.
+    pub fn toggle(&mut self) {
+        if let Some(id) = self.visible().get(self.cursor).copied() && !self.folded.remove(&id) {
+            self.folded.insert(id);
+        }
+        self.follow_cursor = true;
+    }
+
+    /// Unfolds everything when something is folded, otherwise folds every
+    /// topic and daily breakdown so only their headers remain.
+    pub fn toggle_all(&mut self) {
+        let current = self.visible().get(self.cursor).copied();
+        if self.folded.is_empty() {
+            let ids: Vec<SectionId> = self.visible().into_iter().filter(|id| !matches!(id, SectionId::Project(_))).collect();
+            self.folded.extend(ids);
+        } else {
+            self.folded.clear();
+        }
+        let visible = self.visible();
+        self.cursor = current.and_then(|id| visible.iter().position(|v| *v == id)).unwrap_or(0);
+        self.follow_cursor = true;
+    }
+}
+
+/// One earlier exchange in the summary popup.
+#[derive(Clone, Debug)]
+pub struct ChatTurn {
+    /// The follow-up question; `None` for the generated summary itself.
+    pub question: Option<String>,
+    pub answer: String,
+}
+
+impl PopupQuote {
+    /// Forgets the previous conversation and any prompt under review.
+    /// Follow-ups are only possible with a `prompt`.
+    pub fn start_conversation(&mut self, prompt: Option<String>) {
+        self.preview = None;
+        self.sections = None;
+        self.offline = false;
+        self.prompt = prompt;
+        self.chat.clear();
+        self.question = None;
+        self.chat_input = None;
+    }
+
+    /// The structured summary while the popup shows it as sections, i.e.
+    /// before any follow-up.
+    pub fn shown_sections(&mut self) -> Option<&mut SummarySections> {
+        if self.loading || !self.chat.is_empty() || self.question.is_some() {
+            return None;
+        }
+        self.sections.as_mut()
+    }
+
+    /// The conversation so far as (user, model) message pairs, starting with the
+    /// prompt. Failed answers are left out so they do not confuse the model.
+    pub fn chat_history(&self) -> Vec<(String, String)> {
+        let Some(prompt) = &self.prompt else {
+            return vec![];
+        };
+        self.chat
+            .iter()
+            .map(|turn| (turn.question.clone().unwrap_or_else(|| prompt.clone()), turn.answer.clone()))
+            .filter(|(_, answer)| !crate::network::is_error_text(answer))
+            .collect()
+    }
 }
 
 /// State for selected/marked commits.
@@ -23,4 +202,159 @@ pub struct PopupQuote {
 pub struct SelectedCommits {
     pub set: HashSet<String>,
     pub popup_visible: bool,
-}
\ No newline at end of file
+}
+/// State for the custom date range dialog.
+#[derive(Debug)]
+pub struct DateRangeDialog {
+    pub visible: bool,
+    /// Day under the calendar cursor.
+    pub cursor: NaiveDate,
+    /// Start day once picked; the next pick sets the end.
+    pub from: Option<NaiveDate>,
+}
+
+impl DateRangeDialog {
+    pub fn new(today: NaiveDate) -> Self {
+        DateRangeDialog { visible: false, cursor: today, from: None }
+    }
+}
+
+/// State for the `/` search bar over the commit list.
+#[derive(Debug, Default)]
+pub struct SearchState {
+    /// True while the search bar has keyboard focus.
+    pub active: bool,
+    pub query: String,
+    /// Compiled query; invalid regexes fall back to a literal match.
+    pub regex: Option<Regex>,
+    pub invalid_regex: bool,
+    /// Send only the filtered commits to the AI summary.
+    pub filter_summary: bool,
+}
+
+impl SearchState {
+    pub fn set_query(&mut self, query: String) {
+        self.invalid_regex = false;
+        self.regex = if query.is_empty() {
+            None
+        } else {
+            match RegexBuilder::new(&query).case_insensitive(true).build() {
+                Ok(re) => Some(re),
+                Err(_) => {
+                    self.invalid_regex = true;
+                    RegexBuilder::new(&regex::escape(&query)).case_insensitive(true).build().ok()
+                }
+            }
+        };
+        self.query = query;
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.regex.is_some()
+    }
+}
+
+/// State for the author picker panel.
+#[derive(Debug, Default)]
+pub struct AuthorPicker {
+    pub visible: bool,
+    /// Everyone with commits in the current range.
+    pub authors: Vec<AuthorCount>,
+    pub cursor: usize,
+    /// Emails ticked in the open panel, applied on Enter.
+    pub pending: HashSet<String>,
+    /// Emails currently applied as `--author` filters.
+    pub selected: Vec<String>,
+}
+
+impl AuthorPicker {
+    /// Display names of the applied authors, for headers and prompts.
+    pub fn selected_names(&self) -> Vec<String> {
+        self.selected
+            .iter()
+            .map(|email| {
+                self.authors
+                    .iter()
+                    .find(|a| &a.email == email)
+                    .map(|a| format!("{} <{}>", a.name, a.email))
+                    .unwrap_or_else(|| email.clone())
+            })
+            .collect()
+    }
+}
+
+/// The two fields of the standup notes editor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NotesField {
+    #[default]
+    Plans,
+    Blockers,
+}
+
+/// State for the standup notes editor. Text is edited at the end of the field.
+#[derive(Debug, Default)]
+pub struct NotesEditor {
+    pub visible: bool,
+    pub field: NotesField,
+    pub date: NaiveDate,
+    pub notes: DayNotes,
+}
+
+/// State for the summary history browser.
+#[derive(Debug, Default)]
+pub struct HistoryView {
+    pub visible: bool,
+    /// Newest first, as loaded when the view was opened.
+    pub entries: Vec<HistoryEntry>,
+    /// Index into the filtered entries.
+    pub cursor: usize,
+    pub query: String,
+    pub searching: bool,
+    /// Id of the entry marked as the first side of a diff.
+    pub marked: Option<String>,
+    /// Diff between the marked and the selected summary, when shown.
+    pub diff: Option<Vec<(ChangeTag, String)>>,
+    pub diff_scroll: u16,
+    pub message: Option<String>,
+}
+
+impl HistoryView {
+    /// Entries matching the search query.
+    pub fn filtered(&self) -> Vec<&HistoryEntry> {
+        self.entries.iter().filter(|e| self.query.is_empty() || e.matches(&self.query)).collect()
+    }
+
+    pub fn selected(&self) -> Option<&HistoryEntry> {
+        self.filtered().get(self.cursor).copied()
+    }
+}
+
+/// State for the publish confirmation dialog.
+#[derive(Debug, Default)]
+pub struct PublishDialog {
+    pub visible: bool,
+    /// Index into the configured publish targets.
+    pub cursor: usize,
+}
+
+/// State for the export path prompt opened by `e`.
+#[derive(Debug, Default)]
+pub struct ExportDialog {
+    pub visible: bool,
+    /// Target path, prefilled from `export_path`; placeholders are expanded on export.
+    pub path: String,
+    /// Set when the target exists; another Enter overwrites it.
+    pub confirm_overwrite: bool,
+}
+
+/// State for the prompt preset picker shown before a summary is requested.
+#[derive(Debug, Default)]
+pub struct PresetPicker {
+    pub visible: bool,
+    /// Index into the loaded presets.
+    pub cursor: usize,
+    /// Picked preset, taken by the summary request.
+    pub chosen: Option<String>,
+    /// Preset of the last summary, reused when regenerating.
+    pub last: Option<String>,
+}
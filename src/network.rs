@@ -1,36 +1,123 @@
 use std::error::Error;
+use anyhow::{anyhow, Result};
+use gemini_rs::types::{Content, Part, Response, Role};
 use crate::config;
 // use reqwest;
 // use serde_json;
 
+/// Where summaries come from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Provider {
+    Gemini,
+    /// Built from the commits on this machine, see `offline_summary`.
+    Offline,
+}
+
+impl Provider {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "gemini" => Ok(Provider::Gemini),
+            "offline" | "local" => Ok(Provider::Offline),
+            other => Err(anyhow!("Unknown provider '{}'. Use \"gemini\" or \"offline\".", other)),
+        }
+    }
+
+    /// The provider a summary comes from now: Gemini falls back to the
+    /// offline summary without an API key.
+    pub fn effective(self) -> Self {
+        if self == Provider::Gemini && std::env::var("GEMINI_API_KEY").is_err() {
+            Provider::Offline
+        } else {
+            self
+        }
+    }
+}
+
 /// Sends the commit list and a summary prompt to Gemini using the specified model, returns the summary text.
 pub async fn fetch_gemini_commit_summary(prompt: &str, _lang: &str, model: &str) -> Result<String, Box<dyn std::error::Error>> {
     let user_message = prompt;
     let response = match gemini_rs::chat(model).send_message(user_message).await {
         Ok(r) => r,
-        Err(e) => {
-            let msg = if let Some(inner) = e.source() {
-                let s = inner.to_string();
-                if s.contains("API key must be set") || s.contains("GEMINI_API_KEY") || s.contains("401") {
-                    let config_path = config::get_user_config_path().display().to_string();
-                    format!(
-                        "Gemini API key not found.\n\nPlease add it to your configuration file at:\n{}\n\nOr set it as an environment variable: export GEMINI_API_KEY=your-key",
-                        config_path
-                    )
-                } else {
-                    format!("Gemini API error: {}", s)
-                }
-            } else {
-                format!("Gemini API error: {}", e)
-            };
-            return Ok(msg);
-        }
+        Err(e) => return Ok(api_error_text(e)),
+    };
+    Ok(response_text(&response))
+}
+
+/// Like `fetch_gemini_commit_summary`, but in JSON mode with the structured
+/// summary's response schema. The text still needs `structured::parse`.
+pub async fn fetch_gemini_json(prompt: &str, model: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut chat = gemini_rs::chat(model).to_json().response_schema(crate::structured::response_schema());
+    let response = match chat.send_message(prompt).await {
+        Ok(r) => r,
+        Err(e) => return Ok(api_error_text(e)),
     };
-    let text = response.candidates
-        .get(0)
-        .and_then(|c| c.content.parts.get(0))
+    Ok(response_text(&response))
+}
+
+/// Sends a follow-up `message` after the earlier (user, model) message pairs
+/// and returns the answer. Errors are returned as text like the summary's.
+pub async fn fetch_gemini_chat(history: &[(String, String)], message: &str, model: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut chat = gemini_rs::chat(model);
+    for (user, answer) in history {
+        chat.history_mut().push(Content { role: Role::User, parts: vec![Part::text(user)] });
+        chat.history_mut().push(Content { role: Role::Model, parts: vec![Part::text(answer)] });
+    }
+    let response = match chat.send_message(message).await {
+        Ok(r) => r,
+        Err(e) => return Ok(api_error_text(e)),
+    };
+    Ok(response_text(&response))
+}
+
+fn api_error_text(e: gemini_rs::Error) -> String {
+    if let Some(inner) = e.source() {
+        let s = inner.to_string();
+        if s.contains("API key must be set") || s.contains("GEMINI_API_KEY") || s.contains("401") {
+            let config_path = config::get_user_config_path().display().to_string();
+            format!(
+                "Gemini API key not found.\n\nPlease add it to your configuration file at:\n{}\n\nOr set it as an environment variable: export GEMINI_API_KEY=your-key",
+                config_path
+            )
+        } else {
+            format!("Gemini API error: {}", s)
+        }
+    } else {
+        format!("Gemini API error: {}", e)
+    }
+}
+
+fn response_text(response: &Response) -> String {
+    response.candidates.first()
+        .and_then(|c| c.content.parts.first())
         .and_then(|p| p.text.as_ref())
         .map(|s| s.trim().to_string())
-        .unwrap_or_else(|| "No summary received.".to_string());
-    Ok(text)
-}
\ No newline at end of file
+        .unwrap_or_else(|| "No summary received.".to_string())
+}
+/// Responses that report a problem instead of a summary; they are neither cached nor archived.
+pub fn is_error_text(text: &str) -> bool {
+    text.trim().is_empty()
+        || text.starts_with("Gemini error:")
+        || text.starts_with("Gemini API")
+        || text == "No summary received."
+}
+
+/// Like `fetch_gemini_commit_summary`, but answers repeated prompts from the
+/// response cache for `cache_hours`. `refresh` skips the lookup and stores the
+/// new response. `json` asks for a structured summary instead of text.
+/// Returns the text and whether it came from the cache.
+pub async fn fetch_summary_cached(prompt: &str, lang: &str, model: &str, cache_hours: u64, refresh: bool, json: bool) -> Result<(String, bool), Box<dyn std::error::Error>> {
+    let provider = if json { "gemini-json" } else { "gemini" };
+    let key = crate::cache::cache_key(provider, model, prompt);
+    if !refresh && let Some(text) = crate::cache::lookup(&key, cache_hours) {
+        return Ok((text, true));
+    }
+    let text = if json {
+        fetch_gemini_json(prompt, model).await?
+    } else {
+        fetch_gemini_commit_summary(prompt, lang, model).await?
+    };
+    if cache_hours > 0 && !is_error_text(&text) {
+        let _ = crate::cache::store(&key, provider, model, &text);
+    }
+    Ok((text, false))
+}
@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::PathBuf;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use crate::config::get_user_config_path;
+
+/// Hand-written standup notes for one day: what is planned and what blocks it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DayNotes {
+    #[serde(default)]
+    pub plans: String,
+    #[serde(default)]
+    pub blockers: String,
+}
+
+impl DayNotes {
+    /// `notes/YYYY-MM-DD.toml` next to the user's `whid.toml`.
+    fn path(date: NaiveDate) -> PathBuf {
+        let mut path = get_user_config_path();
+        path.pop();
+        path.push("notes");
+        path.push(format!("{}.toml", date.format("%Y-%m-%d")));
+        path
+    }
+
+    /// Loads the notes of `date`; a missing or unreadable file gives empty notes.
+    pub fn load(date: NaiveDate) -> Self {
+        fs::read_to_string(Self::path(date))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Saves the notes; empty notes remove the day's file.
+    pub fn save(&self, date: NaiveDate) -> Result<()> {
+        let path = Self::path(date);
+        if self.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plans.trim().is_empty() && self.blockers.trim().is_empty()
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{BTreeMap, BTreeSet};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use crate::export::ExportCommit;
+use crate::prompts::PromptVars;
+use crate::utils::TICKET_REGEX;
+
+/// Conventional commit types and the topic they are listed under, in output order.
+const TOPICS: [(&str, &str); 11] = [
+    ("feat", "Features"),
+    ("fix", "Fixes"),
+    ("perf", "Performance"),
+    ("refactor", "Refactoring"),
+    ("docs", "Documentation"),
+    ("test", "Tests"),
+    ("build", "Build & CI"),
+    ("ci", "Build & CI"),
+    ("style", "Style"),
+    ("chore", "Chores"),
+    ("revert", "Reverts"),
+];
+const OTHER: &str = "Other changes";
+/// Lines per topic in the overall summary before the rest is counted.
+const MAX_DETAILS: usize = 4;
+/// Changes named per day in the daily breakdown.
+const MAX_PER_DAY: usize = 5;
+
+static CONVENTIONAL: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\w+)(?:\(([^)]*)\))?(!)?:\s*(.*)$").unwrap());
+
+/// One commit, reduced to what the summary shows.
+struct Change {
+    day: String,
+    topic: &'static str,
+    /// Scope and description, without ticket keys.
+    text: String,
+    tickets: Vec<String>,
+}
+
+impl Change {
+    /// `with_author` names the author after the change, for lists with several.
+    fn new(commit: ExportCommit, with_author: bool) -> Self {
+        let subject = commit.subject.lines().next().unwrap_or_default();
+        // Linked commit lines end with " <url>"
+        let subject = match subject.rsplit_once(" <http") {
+            Some((subject, rest)) if rest.ends_with('>') => subject,
+            _ => subject,
+        };
+        let (topic, scope, breaking, description) = match CONVENTIONAL.captures(subject) {
+            Some(caps) => {
+                let kind = caps[1].to_lowercase();
+                let topic = TOPICS.iter().find(|(t, _)| *t == kind).map(|(_, topic)| *topic);
+                match topic {
+                    Some(topic) => (topic, caps.get(2).map(|m| m.as_str().trim().to_string()), caps.get(3).is_some(), caps[4].to_string()),
+                    None => (OTHER, None, false, subject.to_string()),
+                }
+            }
+            None => (OTHER, None, false, subject.to_string()),
+        };
+        let stripped = TICKET_REGEX.replace_all(&description, "");
+        let stripped = stripped.split_whitespace().collect::<Vec<_>>().join(" ");
+        let stripped = stripped.trim_matches(|c: char| c == ':' || c == '-' || c == ',' || c.is_whitespace());
+        let description = if stripped.is_empty() { description.trim() } else { stripped };
+        let mut text = match scope.filter(|s| !s.is_empty()) {
+            Some(scope) => format!("{}: {}", scope, description),
+            None => description.to_string(),
+        };
+        if breaking {
+            text.push_str(" (breaking)");
+        }
+        if let Some(author) = commit.author.filter(|_| with_author) {
+            text = format!("{} ({})", text, author);
+        }
+        Change {
+            day: commit.date.get(..10).unwrap_or(&commit.date).to_string(),
+            topic,
+            text,
+            tickets: commit.tickets,
+        }
+    }
+}
+
+/// A summary built from the commits alone, for when no AI is available. It has
+/// the shape of the default prompt's example output: per repo an overall
+/// summary by topic and ticket, then one line per day. Headings are English.
+pub fn offline_summary(vars: &PromptVars) -> String {
+    let commits: Vec<(String, Vec<ExportCommit>)> = vars.commit_data
+        .iter()
+        .filter(|(_, lines)| !lines.is_empty())
+        .map(|(repo, lines)| {
+            let name = repo.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let commits = lines.iter().map(|line| ExportCommit::parse(&name, line, vars.filter_by_user)).collect();
+            (name, commits)
+        })
+        .collect();
+    let authors: BTreeSet<&str> = commits.iter().flat_map(|(_, c)| c.iter()).filter_map(|c| c.author.as_deref()).collect();
+    let with_author = authors.len() > 1;
+    let repos: Vec<(String, Vec<Change>)> = commits.into_iter()
+        .map(|(name, commits)| (name, commits.into_iter().map(|c| Change::new(c, with_author)).collect()))
+        .collect();
+    if repos.is_empty() {
+        return format!("## {} - Timeframe: {} - {}\n\nNo commits in this period.", vars.project, vars.from, vars.to);
+    }
+    let single = repos.len() == 1;
+    let mut sections: Vec<String> = repos.iter()
+        .map(|(name, changes)| repo_section(if single { vars.project } else { name }, changes, vars))
+        .collect();
+    if !vars.forge_activity.trim().is_empty() {
+        sections.push(format!("*Reviews & PRs*:\n{}", vars.forge_activity.trim()));
+    }
+    if !vars.plans.trim().is_empty() || !vars.blockers.trim().is_empty() {
+        let blockers = if vars.blockers.trim().is_empty() { "None" } else { vars.blockers.trim() };
+        sections.push(format!("*Today*:\n{}\n\n*Blockers*:\n{}", vars.plans.trim(), blockers));
+    }
+    sections.join("\n\n---\n\n")
+}
+
+fn repo_section(project: &str, changes: &[Change], vars: &PromptVars) -> String {
+    let days: BTreeMap<&str, Vec<&Change>> = changes.iter().fold(BTreeMap::new(), |mut days, change| {
+        days.entry(change.day.as_str()).or_insert_with(Vec::new).push(change);
+        days
+    });
+    let mut tickets: Vec<&str> = vec![];
+    for ticket in changes.iter().flat_map(|c| c.tickets.iter()) {
+        if !tickets.contains(&ticket.as_str()) {
+            tickets.push(ticket);
+        }
+    }
+    let mut out = format!("## {} - Timeframe: {} - {}\n\n*Overall summary*:\n", project, vars.from, vars.to);
+    out.push_str(&format!(
+        "- {} {} on {} {}{}\n",
+        changes.len(),
+        if changes.len() == 1 { "commit" } else { "commits" },
+        days.len(),
+        if days.len() == 1 { "day" } else { "days" },
+        if tickets.is_empty() { String::new() } else { format!(", relates to {}", join_and(&tickets)) }
+    ));
+    let mut topics: Vec<&str> = vec![];
+    for topic in TOPICS.iter().map(|(_, topic)| *topic).chain([OTHER]) {
+        if !topics.contains(&topic) {
+            topics.push(topic);
+        }
+    }
+    for topic in topics {
+        let in_topic: Vec<&Change> = changes.iter().filter(|c| c.topic == topic).collect();
+        if in_topic.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("- *{}*\n", topic));
+        let details = topic_details(&in_topic, vars);
+        for detail in details.iter().take(MAX_DETAILS) {
+            out.push_str(&format!("    - {}\n", detail));
+        }
+        if details.len() > MAX_DETAILS {
+            out.push_str(&format!("    - … and {} more\n", details.len() - MAX_DETAILS));
+        }
+    }
+    out.push_str("\nDaily breakdown:\n");
+    for (day, day_changes) in &days {
+        let mut texts: Vec<&str> = day_changes.iter().map(|c| c.text.as_str()).take(MAX_PER_DAY).collect();
+        let more = day_changes.len().saturating_sub(MAX_PER_DAY);
+        let more = if more > 0 { format!(" and {} more", more) } else { String::new() };
+        texts.dedup();
+        let mut day_tickets: Vec<&str> = vec![];
+        for ticket in day_changes.iter().flat_map(|c| c.tickets.iter()) {
+            if !day_tickets.contains(&ticket.as_str()) {
+                day_tickets.push(ticket);
+            }
+        }
+        let relates = if day_tickets.is_empty() { String::new() } else { format!(" – relates to {}", join_and(&day_tickets)) };
+        out.push_str(&format!("- *{}*: {}{}{}\n", day, texts.join("; "), more, relates));
+    }
+    out.trim_end().to_string()
+}
+
+/// One line per ticket with its title when cached, then one per change without a ticket.
+fn topic_details(changes: &[&Change], vars: &PromptVars) -> Vec<String> {
+    let mut by_ticket: Vec<(&str, Vec<&str>)> = vec![];
+    let mut loose: Vec<String> = vec![];
+    for change in changes {
+        match change.tickets.first() {
+            Some(ticket) => match by_ticket.iter_mut().find(|(t, _)| t == ticket) {
+                Some((_, texts)) => texts.push(&change.text),
+                None => by_ticket.push((ticket, vec![&change.text])),
+            },
+            None => loose.push(change.text.clone()),
+        }
+    }
+    by_ticket.into_iter()
+        .map(|(ticket, texts)| match vars.tickets.iter().find(|t| t.key == ticket) {
+            Some(info) => format!("*{}* {}: {}", ticket, info.title, texts.join("; ")),
+            None => format!("*{}*: {}", ticket, texts.join("; ")),
+        })
+        .chain(loose)
+        .collect()
+}
+
+/// "A", "A and B", "A, B and C"
+fn join_and(items: &[&str]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::utils::CommitData;
+
+    fn vars(commit_data: &CommitData, filter_by_user: bool) -> PromptVars<'_> {
+        PromptVars {
+            from: "2024-05-01",
+            to: "2024-05-02",
+            project: "web",
+            interval: "48h",
+            lang: "en",
+            commits: "",
+            authors: &[],
+            ticket_details: "",
+            forge_activity: "",
+            plans: "",
+            blockers: "",
+            commit_data,
+            filter_by_user,
+            tickets: &[],
+        }
+    }
+
+    fn repo(name: &str, lines: &[&str]) -> (PathBuf, Vec<String>) {
+        (PathBuf::from("/src").join(name), lines.iter().map(|l| l.to_string()).collect())
+    }
+
+    #[test]
+    fn groups_conventional_commits_and_breaks_down_days() {
+        let commits = vec![repo("web", &[
+            "a1|2024-05-01 09:00|feat(auth): CPT-12 add login form",
+            "a2|2024-05-01 11:00|fix: CPT-12 handle empty passwords",
+            "a3|2024-05-02 10:00|docs: describe the setup",
+            "a4|2024-05-02 12:00|Update dependencies",
+            "a5|2024-05-02 15:00|feat!: drop the old API",
+        ])];
+        assert_eq!(
+            offline_summary(&vars(&commits, true)),
+            "## web - Timeframe: 2024-05-01 - 2024-05-02\n\n\
+             *Overall summary*:\n\
+             - 5 commits on 2 days, relates to CPT-12\n\
+             - *Features*\n    - *CPT-12*: auth: add login form\n    - drop the old API (breaking)\n\
+             - *Fixes*\n    - *CPT-12*: handle empty passwords\n\
+             - *Documentation*\n    - describe the setup\n\
+             - *Other changes*\n    - Update dependencies\n\n\
+             Daily breakdown:\n\
+             - *2024-05-01*: auth: add login form; handle empty passwords – relates to CPT-12\n\
+             - *2024-05-02*: describe the setup; Update dependencies; drop the old API (breaking)"
+        );
+    }
+
+    #[test]
+    fn names_authors_and_repos_when_there_are_several() {
+        let commits = vec![
+            repo("web", &["a1|2024-05-01 09:00|Alice|fix: typo"]),
+            repo("api", &["b1|2024-05-01 10:00|Bob|chore: bump deps"]),
+            repo("docs", &[]),
+        ];
+        let summary = offline_summary(&vars(&commits, false));
+        let sections: Vec<&str> = summary.split("\n\n---\n\n").collect();
+        assert_eq!(sections.len(), 2);
+        assert!(sections[0].starts_with("## web - Timeframe"), "{}", summary);
+        assert!(sections[0].contains("- *2024-05-01*: typo (Alice)"), "{}", summary);
+        assert!(sections[1].starts_with("## api - Timeframe"), "{}", summary);
+        assert!(sections[1].contains("- *Chores*\n    - bump deps (Bob)"), "{}", summary);
+    }
+
+    #[test]
+    fn caps_busy_days_and_handles_empty_ranges() {
+        let lines: Vec<String> = (1..=7).map(|i| format!("a{}|2024-05-01 0{}:00|fix: bug {}", i, i, i)).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let summary = offline_summary(&vars(&vec![repo("web", &lines)], true));
+        assert!(summary.contains("    - … and 3 more\n"), "{}", summary);
+        assert!(summary.ends_with("- *2024-05-01*: bug 1; bug 2; bug 3; bug 4; bug 5 and 2 more"), "{}", summary);
+        assert_eq!(
+            offline_summary(&vars(&vec![repo("web", &[])], true)),
+            "## web - Timeframe: 2024-05-01 - 2024-05-02\n\nNo commits in this period."
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use crate::config::get_user_config_path;
+use crate::prompts::{prompt_en, render_template, PromptVars};
+
+/// Where a preset's prompt comes from.
+#[derive(Clone, Debug)]
+pub enum PresetSource {
+    /// The built-in standup prompt.
+    Standup,
+    /// A built-in template.
+    BuiltIn(&'static str),
+    /// A template file: `custom_prompt_path`, `--prompt` or the presets directory.
+    File(PathBuf),
+}
+
+/// A named prompt, picked before a summary is requested.
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    pub source: PresetSource,
+}
+
+const WEEKLY: &str = r#"
+IMPORTANT: The prompt is english, but the generated output should be in {lang} language
+
+Write a weekly status update for {project} covering {from} to {to}.
+
+- Start with two or three sentences on what the week was about
+- Then list the main topics as bullet points, each with one line on what changed and why it matters
+- End with a short "Next" section only if the commits clearly point to unfinished work
+- Mention ticket numbers (format: [letter code]-[number sequence]) next to the topic they belong to
+- Use markdown, preserve it in the output
+
+Commit Data:
+{commits}
+
+{ticket_details}
+{forge_activity}
+"#;
+
+const RELEASE_NOTES: &str = r###"
+IMPORTANT: The prompt is english, but the generated output should be in {lang} language
+
+Write release notes for {project} from the commits between {from} and {to}.
+
+- Group the changes under "## Features", "## Fixes" and "## Other changes"; leave out empty groups
+- Write for users of the software: describe the effect of a change, not its implementation
+- Leave out purely internal changes such as refactorings, CI and formatting
+- One bullet per change, ending with the ticket number if there is one
+- Use markdown, preserve it in the output
+
+Commit Data:
+{commits}
+
+{ticket_details}
+"###;
+
+const BRAG: &str = r#"
+IMPORTANT: The prompt is english, but the generated output should be in {lang} language
+
+Write entries for my brag document (a running record of my accomplishments) from my commits between {from} and {to}.
+
+- One bullet per accomplishment, starting with a strong verb
+- Emphasize impact: what got better for users, the team or the codebase
+- Merge small related commits into one accomplishment
+- Mention reviews and pull requests I helped with as their own bullets
+- Use markdown, preserve it in the output
+
+Commit Data:
+{commits}
+
+{ticket_details}
+{forge_activity}
+"#;
+
+const MANAGER: &str = r#"
+IMPORTANT: The prompt is english, but the generated output should be in {lang} language
+
+Write a short update for my manager about my work on {project} from {from} to {to}.
+
+- At most five bullet points, in plain language without technical detail
+- Focus on progress, finished work and anything that needs a decision
+- Add a "Blockers" line only if there are any: {blockers}
+- Use markdown, preserve it in the output
+
+Commit Data:
+{commits}
+
+{ticket_details}
+"#;
+
+/// Built-in presets besides the standup prompt: name, description, template.
+const BUILT_IN: [(&str, &str, &str); 4] = [
+    ("weekly", "Weekly status by topic", WEEKLY),
+    ("release-notes", "User-facing release notes", RELEASE_NOTES),
+    ("brag", "Brag document entries", BRAG),
+    ("manager", "Short update for your manager", MANAGER),
+];
+
+/// `~/.config/whid/presets`, next to the user configuration.
+pub fn presets_dir() -> PathBuf {
+    let mut path = get_user_config_path();
+    path.set_file_name("presets");
+    path
+}
+
+/// The built-in presets, "custom" for a configured custom prompt, then the
+/// `.txt` and `.md` files in the presets directory, named after the file. A
+/// file with the name of a built-in preset replaces it.
+pub fn load_presets(custom_prompt_path: Option<&str>) -> Vec<Preset> {
+    load_presets_from(custom_prompt_path, &presets_dir())
+}
+
+fn load_presets_from(custom_prompt_path: Option<&str>, dir: &Path) -> Vec<Preset> {
+    let mut presets = vec![Preset {
+        name: "standup".to_string(),
+        description: "Daily standup (default prompt)".to_string(),
+        source: PresetSource::Standup,
+    }];
+    presets.extend(BUILT_IN.iter().map(|(name, description, template)| Preset {
+        name: name.to_string(),
+        description: description.to_string(),
+        source: PresetSource::BuiltIn(template),
+    }));
+    if let Some(path) = custom_prompt_path.filter(|p| !p.trim().is_empty()) {
+        presets.push(Preset {
+            name: "custom".to_string(),
+            description: format!("Custom prompt {}", path),
+            source: PresetSource::File(PathBuf::from(shellexpand::tilde(path).to_string())),
+        });
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "txt" || ext == "md"));
+    files.sort();
+    for path in files {
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let preset = Preset { description: format!("From {}", path.display()), name, source: PresetSource::File(path) };
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+    presets
+}
+
+pub fn find<'a>(presets: &'a [Preset], name: &str) -> Option<&'a Preset> {
+    presets.iter().find(|p| p.name == name)
+}
+
+impl Preset {
+    /// Builds the summary prompt. Like `--prompt`, an unreadable template file
+    /// falls back to the standup prompt; template errors are returned.
+    pub fn build_prompt(&self, vars: &PromptVars) -> Result<String> {
+        match &self.source {
+            PresetSource::Standup => Ok(prompt_en(vars)),
+            PresetSource::BuiltIn(template) => render_template(&self.name, template, vars),
+            PresetSource::File(path) => crate::prompts::build_prompt(Some(&path.to_string_lossy()), vars),
+        }
+    }
+}
+
+/// Which preset a summary starts with.
+#[derive(Clone, Debug, Default)]
+pub struct PresetDefaults {
+    /// `--preset`, or "custom" with `--prompt`.
+    pub cli: Option<String>,
+    /// Repo directory name to preset, from `[repo_presets]`.
+    pub by_repo: BTreeMap<String, String>,
+    /// `default_preset`; empty means automatic.
+    pub default: String,
+}
+
+impl PresetDefaults {
+    /// `--preset` first, then the repo's preset when a single repo is
+    /// summarized, then `default_preset`, then the custom prompt if one is
+    /// configured, and finally the standup prompt. Unknown names are skipped.
+    pub fn resolve<'a>(&self, presets: &'a [Preset], repo: Option<&str>) -> &'a Preset {
+        let by_repo = repo.and_then(|repo| self.by_repo.get(repo));
+        [self.cli.as_deref(), by_repo.map(String::as_str), Some(self.default.as_str()), Some("custom")]
+            .into_iter()
+            .flatten()
+            .find_map(|name| find(presets, name))
+            .unwrap_or(&presets[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(presets: &[Preset]) -> Vec<&str> {
+        presets.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    fn defaults(cli: Option<&str>, default: &str) -> PresetDefaults {
+        PresetDefaults {
+            cli: cli.map(str::to_string),
+            by_repo: BTreeMap::from([("billing".to_string(), "release-notes".to_string())]),
+            default: default.to_string(),
+        }
+    }
+
+    #[test]
+    fn resolves_cli_then_repo_then_default_then_custom() {
+        let builtin = load_presets_from(None, Path::new("/nonexistent"));
+        let with_custom = load_presets_from(Some("~/prompt.txt"), Path::new("/nonexistent"));
+        assert_eq!(defaults(Some("brag"), "weekly").resolve(&builtin, Some("billing")).name, "brag");
+        assert_eq!(defaults(None, "weekly").resolve(&builtin, Some("billing")).name, "release-notes");
+        assert_eq!(defaults(None, "weekly").resolve(&builtin, Some("web")).name, "weekly");
+        assert_eq!(defaults(None, "").resolve(&with_custom, None).name, "custom");
+        assert_eq!(defaults(None, "").resolve(&builtin, None).name, "standup");
+        // Unknown names fall through to the next candidate
+        assert_eq!(defaults(Some("missing"), "also-missing").resolve(&builtin, Some("billing")).name, "release-notes");
+    }
+
+    #[test]
+    fn preset_files_add_and_replace_presets() {
+        let dir = std::env::temp_dir().join(format!("whid-presets-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("weekly.md"), "My weekly for {project}").unwrap();
+        fs::write(dir.join("retro.txt"), "Retro {from} to {to}").unwrap();
+        fs::write(dir.join("notes.json"), "{}").unwrap();
+        let presets = load_presets_from(Some("/prompts/team.txt"), &dir);
+        assert_eq!(names(&presets), ["standup", "weekly", "release-notes", "brag", "manager", "custom", "retro"]);
+
+        let commit_data = vec![];
+        let vars = PromptVars {
+            from: "2024-05-01",
+            to: "2024-05-02",
+            project: "web",
+            interval: "48h",
+            lang: "en",
+            commits: "",
+            authors: &[],
+            ticket_details: "",
+            forge_activity: "",
+            plans: "",
+            blockers: "",
+            commit_data: &commit_data,
+            filter_by_user: true,
+            tickets: &[],
+        };
+        assert_eq!(find(&presets, "weekly").unwrap().build_prompt(&vars).unwrap(), "My weekly for web");
+        assert_eq!(find(&presets, "retro").unwrap().build_prompt(&vars).unwrap(), "Retro 2024-05-01 to 2024-05-02");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
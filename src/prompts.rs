@@ -1,6 +1,251 @@
 // Contains prompt strings for commit summaries.
 
-pub fn prompt_en(from: &str, to: &str, project_name: &str, lang: &str, commits: &str) -> String {
+use std::collections::BTreeSet;
+use anyhow::{anyhow, Result};
+use minijinja::Environment;
+use serde::Serialize;
+use crate::export::ExportCommit;
+use crate::tickets::TicketInfo;
+use crate::utils::CommitData;
+
+/// Values substituted into the summary prompt.
+pub struct PromptVars<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub project: &'a str,
+    pub interval: &'a str,
+    pub lang: &'a str,
+    pub commits: &'a str,
+    pub authors: &'a [String],
+    /// Ticket titles and statuses from the issue tracker, one per line.
+    pub ticket_details: &'a str,
+    /// Pull request and review activity from the forge, one per line.
+    pub forge_activity: &'a str,
+    /// Today's plans and blockers from the notes editor.
+    pub plans: &'a str,
+    pub blockers: &'a str,
+    /// The commits in `commits`, for the structured template context.
+    pub commit_data: &'a CommitData,
+    /// Whether the commit lines come without an author, as in "only mine".
+    pub filter_by_user: bool,
+    /// Cached tickets behind `ticket_details`.
+    pub tickets: &'a [TicketInfo],
+}
+
+/// Builds the summary prompt from the custom template at `prompt_path`,
+/// falling back to the default prompt if there is none or it cannot be read.
+/// Fails if the template does not compile or render.
+pub fn build_prompt(prompt_path: Option<&str>, vars: &PromptVars) -> Result<String> {
+    if let Some(path) = prompt_path {
+        match std::fs::read_to_string(path) {
+            Ok(template) => return render_template(path, &template, vars),
+            Err(e) => {
+                eprintln!("Error loading custom prompt '{}': {}. Falling back to default prompt.", path, e);
+            }
+        }
+    }
+    Ok(prompt_en(vars))
+}
+
+/// Renders a prompt template. Templates using `{{ }}`, `{% %}` or `{# #}` are
+/// Jinja templates over `TemplateContext`; others only get their
+/// `{placeholder}`s replaced, as before.
+pub fn render_template(name: &str, template: &str, vars: &PromptVars) -> Result<String> {
+    if !["{{", "{%", "{#"].iter().any(|tag| template.contains(tag)) {
+        return Ok(replace_placeholders(template, vars));
+    }
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    // Keeps the template source for error excerpts, also in release builds
+    env.set_debug(true);
+    env.add_template(name, template).map_err(|e| template_error(name, e))?;
+    env.get_template(name)
+        .and_then(|t| t.render(TemplateContext::new(vars)))
+        .map_err(|e| template_error(name, e))
+}
+
+/// Error message with the template lines around the problem. The excerpt is
+/// a code block so the popup's Markdown rendering keeps it intact.
+fn template_error(name: &str, e: minijinja::Error) -> anyhow::Error {
+    let mut message = format!("Prompt template error in {}:\n\n{}", name, e);
+    // Only the numbered source lines and the marker under the error
+    let excerpt = e.display_debug_info().to_string()
+        .lines()
+        .filter(|line| line.trim_start().starts_with(|c: char| c.is_ascii_digit()) || line.trim_start().starts_with("i "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !excerpt.is_empty() {
+        message.push_str(&format!("\n\n```\n{}\n```", excerpt));
+    }
+    anyhow!(message)
+}
+
+/// Everything a Jinja prompt template can use.
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    from: &'a str,
+    to: &'a str,
+    project: &'a str,
+    interval: &'a str,
+    lang: &'a str,
+    authors: &'a [String],
+    /// The commit list as sent by the default prompt.
+    commits: &'a str,
+    repos: Vec<TemplateRepo>,
+    tickets: &'a [TicketInfo],
+    ticket_details: &'a str,
+    forge_activity: &'a str,
+    notes: TemplateNotes<'a>,
+    stats: TemplateStats,
+}
+
+#[derive(Serialize)]
+struct TemplateRepo {
+    name: String,
+    commits: Vec<TemplateCommit>,
+}
+
+#[derive(Serialize)]
+struct TemplateCommit {
+    hash: String,
+    /// "YYYY-MM-DD HH:MM" as in the commit list, and the day alone.
+    date: String,
+    day: String,
+    author: Option<String>,
+    subject: String,
+    tickets: Vec<String>,
+    url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TemplateNotes<'a> {
+    plans: &'a str,
+    blockers: &'a str,
+}
+
+#[derive(Serialize)]
+struct TemplateStats {
+    commits: usize,
+    repos: usize,
+    authors: usize,
+    days: usize,
+}
+
+impl<'a> TemplateContext<'a> {
+    fn new(vars: &'a PromptVars) -> Self {
+        let repos: Vec<TemplateRepo> = vars.commit_data
+            .iter()
+            .map(|(repo, lines)| {
+                let name = repo.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let commits = lines.iter().map(|line| TemplateCommit::new(ExportCommit::parse(&name, line, vars.filter_by_user))).collect();
+                TemplateRepo { name, commits }
+            })
+            .collect();
+        let all = || repos.iter().flat_map(|r| r.commits.iter());
+        let stats = TemplateStats {
+            commits: all().count(),
+            repos: repos.iter().filter(|r| !r.commits.is_empty()).count(),
+            authors: all().filter_map(|c| c.author.as_deref()).collect::<BTreeSet<_>>().len(),
+            days: all().map(|c| c.day.as_str()).collect::<BTreeSet<_>>().len(),
+        };
+        TemplateContext {
+            from: vars.from,
+            to: vars.to,
+            project: vars.project,
+            interval: vars.interval,
+            lang: vars.lang,
+            authors: vars.authors,
+            commits: vars.commits,
+            repos,
+            tickets: vars.tickets,
+            ticket_details: vars.ticket_details,
+            forge_activity: vars.forge_activity,
+            notes: TemplateNotes { plans: vars.plans, blockers: vars.blockers },
+            stats,
+        }
+    }
+}
+
+impl TemplateCommit {
+    fn new(commit: ExportCommit) -> Self {
+        // Linked commit lines end with " <url>"
+        let (subject, url) = match commit.subject.rsplit_once(" <http") {
+            Some((subject, rest)) if rest.ends_with('>') => (subject.to_string(), Some(format!("http{}", rest.trim_end_matches('>')))),
+            _ => (commit.subject, commit.url),
+        };
+        TemplateCommit {
+            day: commit.date.get(..10).unwrap_or(&commit.date).to_string(),
+            hash: commit.hash,
+            date: commit.date,
+            author: commit.author,
+            subject,
+            tickets: commit.tickets,
+            url,
+        }
+    }
+}
+
+/// Replaces the `{placeholder}`s of a plain prompt template.
+fn replace_placeholders(template: &str, vars: &PromptVars) -> String {
+    template
+        .replace("{from}", vars.from)
+        .replace("{to}", vars.to)
+        .replace("{project}", vars.project)
+        .replace("{projectname}", vars.project)
+        .replace("{interval}", vars.interval)
+        .replace("{lang}", vars.lang)
+        .replace("{authors}", &vars.authors.join(", "))
+        .replace("{ticket_details}", vars.ticket_details)
+        .replace("{forge_activity}", vars.forge_activity)
+        .replace("{plans}", vars.plans)
+        .replace("{blockers}", vars.blockers)
+        .replace("{commits}", vars.commits)
+}
+
+/// An empty note is sent as "None", so the standup format stays the same every day.
+fn standup_note(text: &str) -> &str {
+    match text.trim() {
+        "" => "None",
+        text => text,
+    }
+}
+
+pub fn prompt_en(vars: &PromptVars) -> String {
+    let authors = vars.authors;
+    let authors_rule = if authors.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "- The commits are from these authors: {}. Within each project, add a section per author, headed with the author's name, summarizing their changes\n",
+            authors.join(", ")
+        )
+    };
+    let ticket_block = if vars.ticket_details.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\nTicket details (use the titles to describe what the work was about):\n{}\n",
+            vars.ticket_details
+        )
+    };
+    let forge_block = if vars.forge_activity.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\nPull request and review activity (add a short \"Reviews & PRs\" section for it after the overall summary):\n{}\n",
+            vars.forge_activity
+        )
+    };
+    let standup_block = format!(
+        "\nStandup notes (format the whole output as a classic standup with the sections *Yesterday* for the commit summary and daily breakdown, *Today* for these plans and *Blockers* for these blockers; keep a section that says \"None\" as \"None\"):\nToday I plan:\n{}\nBlockers:\n{}\n",
+        standup_note(vars.plans),
+        standup_note(vars.blockers)
+    );
+    let links_rule = if vars.commits.contains(" <http") {
+        "- Commit lines end with the commit's web URL in angle brackets. When you mention a commit hash, link it as [hash](url)\n"
+    } else {
+        ""
+    };
     format!(
         r#"
 IMPORTANT: The prompt is english, but the generated output should be in {lang} language
@@ -14,10 +259,10 @@ Overall summary: Summarize all changes in the Git history in short, concise bull
 - Use markdown, preserve it in the output, including spaces
 - if no changes for a day, do not include it in the Daily breakdown
 - Dateformat is YYYY-MM-DD
-
+{authors_rule}{links_rule}
 Commit Data: 
 {commits}
-
+{ticket_block}{forge_block}{standup_block}
 Example for the Git history from {from} to {to}:
 
 
@@ -31,11 +276,118 @@ Example for the Git history from {from} to {to}:
 Daily breakdown:
 - [*Date 1*]: [Changes on this day summarized]
 - [*Date 2*]: [Changes on this day summarized]
+"#,
+        from = vars.from,
+        to = vars.to,
+        project_name = vars.project,
+        lang = vars.lang,
+        commits = vars.commits,
+        authors_rule = authors_rule,
+        links_rule = links_rule,
+        ticket_block = ticket_block,
+        forge_block = forge_block,
+        standup_block = standup_block
+    )
+}
+
+/// Prompt for a combined team report: one section per team member.
+pub fn prompt_team(from: &str, to: &str, lang: &str, members: &[String], commits: &str) -> String {
+    format!(
+        r#"
+IMPORTANT: The prompt is english, but the generated output should be in {lang} language
+
+Write a team standup report for the Git history from {from} to {to}.
+The team members are: {members}.
+
+- Create one section per team member, in the order given above, headed with ## [Name]
+- In each section, summarize the member's changes in short, concise bullet points grouped by topic, followed by a daily breakdown with one line per day
+- Mention the project a change belongs to (shown in square brackets in the commit data)
+- If ticket numbers (format: [letter code]-[number sequence]) appear in the commit, add them to the daily overview at the end. e.g. "[...] relates to CPT-2345 and DSG-23212"
+- If a member has no commits, write "No commits in this period." in their section
+- Use markdown, preserve it in the output, including spaces
+- Dateformat is YYYY-MM-DD
+
+Commit Data, grouped by team member:
+{commits}
+
+Example:
+
+## [Name]
+
+*Overall summary*:
+- *[Topic / Topic Headline]*
+    - [Details, up to 4, depending on complexity]
+
+Daily breakdown:
+- [*Date 1*]: [Changes on this day summarized]
 "#,
         from = from,
         to = to,
-        project_name = project_name,
         lang = lang,
+        members = members.join(", "),
         commits = commits
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+
+    fn vars<'a>(plans: &'a str, blockers: &'a str, commit_data: &'a CommitData) -> PromptVars<'a> {
+        PromptVars {
+            from: "2024-05-01",
+            to: "2024-05-02",
+            project: "web",
+            interval: "48h",
+            lang: "en",
+            commits: "abc1234 2024-05-01 10:00 Fix login",
+            authors: &[],
+            ticket_details: "",
+            forge_activity: "",
+            plans,
+            blockers,
+            commit_data,
+            filter_by_user: true,
+            tickets: &[],
+        }
+    }
+
+    #[test]
+    fn plain_templates_only_replace_placeholders() {
+        let commit_data = vec![];
+        let vars = vars("Ship it", "", &commit_data);
+        let prompt = render_template("plain", "{project} {from} to {to} ({interval}, {lang}): {plans} {unknown}\n{commits}", &vars).unwrap();
+        assert_eq!(prompt, "web 2024-05-01 to 2024-05-02 (48h, en): Ship it {unknown}\nabc1234 2024-05-01 10:00 Fix login");
+    }
+
+    #[test]
+    fn jinja_templates_see_repos_and_stats() {
+        let commit_data: CommitData = vec![(PathBuf::from("/src/web"), vec![
+            "abc1234|2024-05-01 10:00|Fix login CPT-12".to_string(),
+            "def5678|2024-05-02 09:30|Add search".to_string(),
+        ])];
+        let template = "{% for repo in repos %}{{ repo.name }}:{% for c in repo.commits %} {{ c.day }} {{ c.subject }}{% if c.tickets %} [{{ c.tickets|join(',') }}]{% endif %};{% endfor %}{% endfor %}\n{{ stats.commits }} commits, {{ stats.days }} days, {project} stays\n";
+        let prompt = render_template("jinja", template, &vars("", "", &commit_data)).unwrap();
+        assert_eq!(prompt, "web: 2024-05-01 Fix login CPT-12 [CPT-12]; 2024-05-02 Add search;\n2 commits, 2 days, {project} stays\n");
+    }
+
+    #[test]
+    fn template_errors_quote_the_broken_line() {
+        let err = render_template("broken.txt", "Summary for {{ project }}\n{% for x in %}\n", &vars("", "", &vec![])).unwrap_err().to_string();
+        assert!(err.starts_with("Prompt template error in broken.txt:"), "{}", err);
+        assert!(err.contains("in broken.txt:2"), "{}", err);
+        assert!(err.contains("```\n   1 | Summary for {{ project }}\n   2 > {% for x in %}\n"), "{}", err);
+        assert!(err.trim_end().ends_with("^^ syntax error\n```"), "{}", err);
+    }
+
+    #[test]
+    fn default_prompt_always_asks_for_a_standup() {
+        let prompt = prompt_en(&vars("", " ", &vec![]));
+        assert!(prompt.contains("*Yesterday*"));
+        assert!(prompt.contains("Today I plan:\nNone\nBlockers:\nNone\n"));
+
+        let prompt = prompt_en(&vars("Ship the release\n", "", &vec![]));
+        assert!(prompt.contains("Today I plan:\nShip the release\nBlockers:\nNone\n"));
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::json;
+use crate::config::PublishTarget;
+use crate::export::ExportDoc;
+
+/// Chat services with incoming webhooks, or a generic HTTP endpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WebhookKind {
+    Slack,
+    Mattermost,
+    Discord,
+    Http,
+}
+
+impl WebhookKind {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "slack" => Ok(WebhookKind::Slack),
+            "mattermost" => Ok(WebhookKind::Mattermost),
+            "discord" => Ok(WebhookKind::Discord),
+            "http" => Ok(WebhookKind::Http),
+            other => Err(anyhow!("Unknown publish kind '{}'. Use slack, mattermost, discord or http.", other)),
+        }
+    }
+}
+
+// Discord rejects messages longer than this
+const DISCORD_MAX_LEN: usize = 2000;
+
+static HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(#{1,6})\s+(.*)$").unwrap());
+static BULLET: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\s*)[-*+]\s+(.*)$").unwrap());
+static HR: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*([-*_])(\s*([-*_]))+\s*$").unwrap());
+static BOLD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*\*(.+?)\*\*|__(.+?)__").unwrap());
+static ITALIC: Lazy<Regex> = Lazy::new(|| Regex::new(r"(^|[^*\w])\*([^*\s][^*]*?)\*").unwrap());
+static LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]+)\]\(([^)\s]+)\)").unwrap());
+
+/// Converts Markdown to Slack mrkdwn: `*bold*`, `_italic_`, `<url|text>`,
+/// bullets and headings as bold lines. Code spans pass through unchanged.
+pub fn to_slack_mrkdwn(markdown: &str) -> String {
+    let mut in_code_block = false;
+    let mut out = vec![];
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            out.push(line.to_string());
+            continue;
+        }
+        if in_code_block {
+            out.push(line.to_string());
+            continue;
+        }
+        if HR.is_match(line) {
+            out.push("──────────".to_string());
+            continue;
+        }
+        let (prefix, text) = if let Some(c) = HEADING.captures(line) {
+            let text = BOLD.replace_all(&c[2], "$1$2").to_string();
+            out.push(format!("*{}*", text.trim()));
+            continue;
+        } else if let Some(c) = BULLET.captures(line) {
+            (format!("{}• ", c[1].replace('\t', "    ")), c[2].to_string())
+        } else {
+            (String::new(), line.to_string())
+        };
+        out.push(format!("{}{}", prefix, slack_inline(&text)));
+    }
+    out.join("\n")
+}
+
+fn slack_inline(text: &str) -> String {
+    // Split on backticks so code spans stay untouched
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                return format!("`{}`", part);
+            }
+            let part = LINK.replace_all(part, "<$2|$1>");
+            let part = ITALIC.replace_all(&part, "${1}_${2}_");
+            BOLD.replace_all(&part, "*$1$2*").to_string()
+        })
+        .collect()
+}
+
+/// Discord understands most Markdown but has no rules and only three heading
+/// levels; long summaries are split into several messages.
+pub fn to_discord_markdown(markdown: &str) -> Vec<String> {
+    let converted: Vec<String> = markdown
+        .lines()
+        .map(|line| {
+            if HR.is_match(line) {
+                String::new()
+            } else if let Some(c) = HEADING.captures(line) {
+                format!("{} {}", "#".repeat(c[1].len().min(3)), &c[2])
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    split_messages(&converted, DISCORD_MAX_LEN)
+}
+
+/// Packs lines into chunks of at most `max` characters. Lines longer than
+/// `max` are cut; chunks with nothing but whitespace are dropped.
+fn split_messages(lines: &[String], max: usize) -> Vec<String> {
+    fn push(chunks: &mut Vec<String>, chunk: String) {
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk);
+        }
+    }
+    let mut chunks = vec![];
+    let mut current = String::new();
+    for line in lines {
+        let mut line: &str = line;
+        while line.chars().count() > max {
+            let cut = line.char_indices().nth(max).map(|(i, _)| i).unwrap_or(line.len());
+            push(&mut chunks, std::mem::take(&mut current));
+            push(&mut chunks, line[..cut].to_string());
+            line = &line[cut..];
+        }
+        let separator = usize::from(!current.is_empty());
+        if current.chars().count() + separator + line.chars().count() > max {
+            push(&mut chunks, std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    push(&mut chunks, current);
+    chunks
+}
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(\w+)(:json)?\}").unwrap());
+
+/// Fills an `http` body template. Placeholders are `{summary}`, `{from}`, `{to}`,
+/// `{interval}`, `{repos}` (comma-separated), `{commits}` (one line per commit)
+/// and `{json}` (the whole export document). `{name:json}` inserts the value as
+/// a quoted JSON string. Unknown placeholders are left as they are.
+pub fn render_body(template: &str, doc: &ExportDoc) -> Result<String> {
+    let mut repos: Vec<&str> = vec![];
+    for commit in &doc.commits {
+        if !repos.contains(&commit.repo.as_str()) {
+            repos.push(&commit.repo);
+        }
+    }
+    let commits = doc.commits.iter()
+        .map(|c| format!("[{}] {} {} {}", c.repo, c.hash, c.date, c.subject.lines().next().unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let json = serde_json::to_string(doc)?;
+    Ok(PLACEHOLDER.replace_all(template, |caps: &regex::Captures| {
+        let value = match &caps[1] {
+            "summary" => doc.summary.clone().unwrap_or_default(),
+            "from" => doc.from.clone(),
+            "to" => doc.to.clone(),
+            "interval" => doc.interval.clone(),
+            "repos" => repos.join(", "),
+            "commits" => commits.clone(),
+            "json" => return json.clone(),
+            _ => return caps[0].to_string(),
+        };
+        if caps.get(2).is_some() { serde_json::Value::String(value).to_string() } else { value }
+    }).into_owned())
+}
+
+fn expand_env(target: &PublishTarget, value: &str) -> Result<String> {
+    Ok(shellexpand::env(value).map_err(|e| anyhow!("{}: {}", target.name, e))?.into_owned())
+}
+
+/// Sends the summary in `doc` to one target.
+pub async fn publish(target: &PublishTarget, doc: &ExportDoc) -> Result<()> {
+    let kind = WebhookKind::parse(&target.kind)?;
+    let url = expand_env(target, &target.url)?;
+    let summary = doc.summary.as_deref().unwrap_or_default();
+    let client = reqwest::Client::new();
+    if kind == WebhookKind::Http {
+        let method = target.method.as_deref().unwrap_or("POST").to_uppercase();
+        let method = reqwest::Method::from_bytes(method.as_bytes()).map_err(|_| anyhow!("{}: invalid method '{}'", target.name, method))?;
+        let mut request = client.request(method, &url);
+        if !target.headers.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+            request = request.header("Content-Type", "application/json");
+        }
+        for (name, value) in &target.headers {
+            request = request.header(name, expand_env(target, value)?);
+        }
+        let body = render_body(target.body.as_deref().unwrap_or("{json}"), doc)?;
+        return check_response(target, request.body(body).send().await?).await;
+    }
+    let payloads = match kind {
+        WebhookKind::Slack => vec![json!({ "text": to_slack_mrkdwn(summary) })],
+        // Mattermost renders standard Markdown
+        WebhookKind::Mattermost => vec![json!({ "text": summary })],
+        WebhookKind::Discord => to_discord_markdown(summary).into_iter().map(|c| json!({ "content": c })).collect(),
+        WebhookKind::Http => unreachable!("handled above"),
+    };
+    for payload in payloads {
+        check_response(target, client.post(&url).json(&payload).send().await?).await?;
+    }
+    Ok(())
+}
+
+async fn check_response(target: &PublishTarget, response: reqwest::Response) -> Result<()> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("{} returned {}: {}", target.name, status, body.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn packs_lines_up_to_the_limit() {
+        let chunks = split_messages(&lines(&["aaaa", "bbbb", "cccc"]), 9);
+        assert_eq!(chunks, vec!["aaaa\nbbbb", "cccc"]);
+    }
+
+    #[test]
+    fn a_line_of_exactly_the_limit_makes_no_empty_chunk() {
+        let chunks = split_messages(&lines(&["aaaa", "bbbbbbbbb", "cc"]), 9);
+        assert_eq!(chunks, vec!["aaaa", "bbbbbbbbb", "cc"]);
+        let chunks = split_messages(&lines(&["bbbbbbbbb"]), 9);
+        assert_eq!(chunks, vec!["bbbbbbbbb"]);
+    }
+
+    #[test]
+    fn cuts_overlong_lines() {
+        let chunks = split_messages(&lines(&["x", "ääääääääääääää", "y"]), 5);
+        assert_eq!(chunks, vec!["x", "äääää", "äääää", "ääää", "y"]);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 5));
+    }
+
+    #[test]
+    fn drops_whitespace_chunks() {
+        let chunks = split_messages(&lines(&["", "  ", "aaaa", "", "", "bbbb", "   "]), 4);
+        assert_eq!(chunks, vec!["aaaa", "bbbb"]);
+        assert!(split_messages(&lines(&["", " "]), 4).is_empty());
+    }
+
+    #[test]
+    fn converts_markdown_to_slack() {
+        let markdown = "## **Project**\n- **bold** and *italic*\n  * [link](https://example.com) `*code*`\n---\n```\n**kept**\n```";
+        assert_eq!(
+            to_slack_mrkdwn(markdown),
+            "*Project*\n• *bold* and _italic_\n  • <https://example.com|link> `*code*`\n──────────\n```\n**kept**\n```"
+        );
+    }
+
+    #[test]
+    fn converts_markdown_to_discord() {
+        let markdown = "# Title\n#### Deep\ntext\n***\nmore";
+        assert_eq!(to_discord_markdown(markdown), vec!["# Title\n### Deep\ntext\n\nmore"]);
+        let long = vec!["x".repeat(1500); 3].join("\n");
+        let messages = to_discord_markdown(&long);
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|m| m.chars().count() <= DISCORD_MAX_LEN));
+    }
+}
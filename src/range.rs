@@ -0,0 +1,417 @@
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use crate::config::IntervalConfig;
+
+/// Timezone used to interpret calendar dates such as `--from` / `--to`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Zone {
+    Local,
+    Named(Tz),
+}
+
+impl Zone {
+    /// Parses the `timezone` setting: "local" (or empty) or an IANA name like "Europe/Berlin".
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        name.parse::<Tz>()
+            .map(Zone::Named)
+            .map_err(|_| anyhow!("Unknown timezone '{}'. Use \"local\" or an IANA name like \"Europe/Berlin\".", name))
+    }
+
+    /// The IANA name to export as `TZ` for child processes, if any.
+    pub fn tz_name(&self) -> Option<&'static str> {
+        match self {
+            Zone::Local => None,
+            Zone::Named(tz) => Some(tz.name()),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Local => Local::now().fixed_offset(),
+            Zone::Named(tz) => Utc::now().with_timezone(tz).fixed_offset(),
+        }
+    }
+
+    /// Converts a wall-clock time in this zone to an absolute timestamp.
+    pub fn at(&self, naive: NaiveDateTime) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Local => resolve_local(&Local, naive),
+            Zone::Named(tz) => resolve_local(tz, naive),
+        }
+    }
+
+    pub fn start_of_day(&self, date: NaiveDate) -> DateTime<FixedOffset> {
+        self.at(date.and_time(NaiveTime::MIN))
+    }
+
+    pub fn end_of_day(&self, date: NaiveDate) -> DateTime<FixedOffset> {
+        self.at(date.and_hms_opt(23, 59, 59).expect("valid end of day"))
+    }
+
+    /// Today's calendar date in this zone.
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    fn localize(&self, ts: &DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Local => ts.with_timezone(&Local).fixed_offset(),
+            Zone::Named(tz) => ts.with_timezone(tz).fixed_offset(),
+        }
+    }
+}
+
+/// Wall-clock times in a DST gap do not exist; they resolve to the first valid
+/// time after the gap, where the clock resumes. Ambiguous times take the earlier one.
+fn resolve_local<Z: TimeZone>(tz: &Z, naive: NaiveDateTime) -> DateTime<FixedOffset> {
+    if let Some(dt) = tz.from_local_datetime(&naive).earliest() {
+        return dt.fixed_offset();
+    }
+    // Gaps start and end on a full minute and last a few hours at most
+    let mut local = naive.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(naive);
+    for _ in 0..24 * 60 {
+        local += chrono::Duration::minutes(1);
+        if let Some(dt) = tz.from_local_datetime(&local).earliest() {
+            return dt.fixed_offset();
+        }
+    }
+    naive.and_utc().fixed_offset()
+}
+
+/// User-supplied `--from` / `--to` bounds, validated as `YYYY-MM-DD`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DateBounds {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateBounds {
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> Result<Self> {
+        let bounds = DateBounds {
+            from: from.map(|s| parse_date("--from", s)).transpose()?,
+            to: to.map(|s| parse_date("--to", s)).transpose()?,
+        };
+        if let (Some(from), Some(to)) = (bounds.from, bounds.to) && from > to {
+            return Err(anyhow!("--from {} is after --to {}", from, to));
+        }
+        Ok(bounds)
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.from.is_some() || self.to.is_some()
+    }
+}
+
+fn parse_date(flag: &str, value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid {} date '{}': expected YYYY-MM-DD", flag, value))
+}
+
+/// A resolved commit time window. The same value feeds git, the header and the AI prompt.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeRange {
+    pub since: DateTime<FixedOffset>,
+    pub until: DateTime<FixedOffset>,
+    pub zone: Zone,
+    /// Label shown in the header: the interval name, or the dates of a custom range.
+    pub label: String,
+}
+
+impl TimeRange {
+    /// Resolves the window: explicit dates win over the selected interval.
+    pub fn resolve(bounds: &DateBounds, interval: &Interval, zone: Zone) -> Self {
+        if bounds.is_set() {
+            let fallback = match interval.kind {
+                IntervalKind::Rolling(d) => d,
+                _ => Duration::from_secs(24 * 3600),
+            };
+            let mut range = Self::from_bounds(bounds, fallback, zone);
+            range.label = format!("{} to {}", range.start_date(), range.end_date());
+            return range;
+        }
+        let mut range = match &interval.kind {
+            IntervalKind::Rolling(d) => Self::from_bounds(&DateBounds::default(), *d, zone),
+            IntervalKind::Calendar(period) => {
+                let (from, to) = period.dates(zone.today());
+                Self::from_bounds(&DateBounds { from: Some(from), to: Some(to) }, Duration::ZERO, zone)
+            }
+            IntervalKind::Fixed(fixed) => Self::from_bounds(fixed, Duration::from_secs(24 * 3600), zone),
+        };
+        range.label = interval.label.clone();
+        range
+    }
+
+    /// `--to` is inclusive until end of day, and a missing start falls back to
+    /// `interval` before the end.
+    fn from_bounds(bounds: &DateBounds, interval: Duration, zone: Zone) -> Self {
+        let now = zone.now();
+        let until = bounds.to.map(|d| zone.end_of_day(d)).unwrap_or(now);
+        let since = match bounds.from {
+            Some(d) => zone.start_of_day(d),
+            None => until - chrono::Duration::from_std(interval).unwrap_or_default(),
+        };
+        TimeRange { since, until, zone, label: String::new() }
+    }
+
+    /// `--since` argument for `git log`, as ISO 8601 with offset.
+    pub fn git_since(&self) -> String {
+        self.since.format("%Y-%m-%dT%H:%M:%S%:z").to_string()
+    }
+
+    /// `--until` argument for `git log`, as ISO 8601 with offset.
+    pub fn git_until(&self) -> String {
+        self.until.format("%Y-%m-%dT%H:%M:%S%:z").to_string()
+    }
+
+    /// Start date (YYYY-MM-DD) in the configured zone.
+    pub fn start_date(&self) -> String {
+        self.zone.localize(&self.since).format("%Y-%m-%d").to_string()
+    }
+
+    /// End date (YYYY-MM-DD) in the configured zone.
+    pub fn end_date(&self) -> String {
+        self.zone.localize(&self.until).format("%Y-%m-%d").to_string()
+    }
+}
+
+/// Calendar-aligned periods, always covering whole days.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CalendarPeriod {
+    Today,
+    Yesterday,
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+    ThisQuarter,
+    LastQuarter,
+}
+
+impl CalendarPeriod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "today" => Some(CalendarPeriod::Today),
+            "yesterday" => Some(CalendarPeriod::Yesterday),
+            "this_week" => Some(CalendarPeriod::ThisWeek),
+            "last_week" => Some(CalendarPeriod::LastWeek),
+            "this_month" => Some(CalendarPeriod::ThisMonth),
+            "last_month" => Some(CalendarPeriod::LastMonth),
+            "this_quarter" => Some(CalendarPeriod::ThisQuarter),
+            "last_quarter" => Some(CalendarPeriod::LastQuarter),
+            _ => None,
+        }
+    }
+
+    /// First and last day of the period relative to `today`. Weeks start on
+    /// Monday; quarters on January, April, July and October 1st.
+    pub fn dates(self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let week_start = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let month_start = today.with_day(1).expect("first of month");
+        let quarter_start = month_start - Months::new(today.month0() % 3);
+        match self {
+            CalendarPeriod::Today => (today, today),
+            CalendarPeriod::Yesterday => {
+                let d = today - Days::new(1);
+                (d, d)
+            }
+            CalendarPeriod::ThisWeek => (week_start, today),
+            CalendarPeriod::LastWeek => (week_start - Days::new(7), week_start - Days::new(1)),
+            CalendarPeriod::ThisMonth => (month_start, today),
+            CalendarPeriod::LastMonth => (month_start - Months::new(1), month_start - Days::new(1)),
+            CalendarPeriod::ThisQuarter => (quarter_start, today),
+            CalendarPeriod::LastQuarter => (quarter_start - Months::new(3), quarter_start - Days::new(1)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntervalKind {
+    /// A window of fixed length ending now.
+    Rolling(Duration),
+    Calendar(CalendarPeriod),
+    /// A fixed date range, as with `--from` / `--to`.
+    Fixed(DateBounds),
+}
+
+/// One entry of the Tab-cycle interval list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interval {
+    pub label: String,
+    pub kind: IntervalKind,
+}
+
+impl Interval {
+    pub fn rolling(label: &str, hours: u64) -> Self {
+        Interval { label: label.to_string(), kind: IntervalKind::Rolling(Duration::from_secs(hours * 3600)) }
+    }
+
+    /// Builds an interval from an `[[intervals]]` entry in `whid.toml`.
+    pub fn from_config(entry: &IntervalConfig) -> Result<Self> {
+        let kind = if let Some(hours) = entry.hours {
+            IntervalKind::Rolling(Duration::from_secs(hours * 3600))
+        } else if let Some(days) = entry.days {
+            IntervalKind::Rolling(Duration::from_secs(days * 24 * 3600))
+        } else if let Some(name) = &entry.calendar {
+            IntervalKind::Calendar(CalendarPeriod::parse(name).ok_or_else(|| {
+                anyhow!("Interval '{}': unknown calendar period '{}'. Use today, yesterday, this_week, last_week, this_month, last_month, this_quarter or last_quarter.", entry.label, name)
+            })?)
+        } else if entry.from.is_some() {
+            IntervalKind::Fixed(DateBounds::parse(entry.from.as_deref(), entry.to.as_deref())?)
+        } else {
+            return Err(anyhow!("Interval '{}' needs one of: hours, days, calendar or from/to", entry.label));
+        };
+        Ok(Interval { label: entry.label.clone(), kind })
+    }
+
+    /// The built-in list used when `whid.toml` defines no intervals.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Interval::rolling("24h", 24),
+            Interval::rolling("48h", 48),
+            Interval::rolling("72h", 72),
+            Interval::rolling("1 week", 7 * 24),
+            Interval::rolling("1 month", 30 * 24),
+        ]
+    }
+
+    /// Whether the `timeframe` CLI argument refers to this interval, by label or legacy name.
+    pub fn matches(&self, timeframe: &str) -> bool {
+        if self.label.eq_ignore_ascii_case(timeframe) {
+            return true;
+        }
+        let hours = match timeframe {
+            "24" | "today" => 24,
+            "48" | "yesterday" => 48,
+            "72" => 72,
+            "week" => 24 * 7,
+            "month" => 24 * 30,
+            _ => match timeframe.parse::<u64>() {
+                Ok(h) => h,
+                Err(_) => return false,
+            },
+        };
+        self.kind == IntervalKind::Rolling(Duration::from_secs(hours * 3600))
+    }
+
+    /// Target of the `w` shortcut.
+    pub fn is_week(&self) -> bool {
+        match &self.kind {
+            IntervalKind::Rolling(d) => *d == Duration::from_secs(7 * 24 * 3600),
+            IntervalKind::Calendar(p) => matches!(p, CalendarPeriod::ThisWeek | CalendarPeriod::LastWeek),
+            IntervalKind::Fixed(_) => false,
+        }
+    }
+}
+
+/// Loads the interval list from the settings, falling back to the built-in defaults.
+pub fn load_intervals(entries: &[IntervalConfig]) -> Result<Vec<Interval>> {
+    if entries.is_empty() {
+        return Ok(Interval::defaults());
+    }
+    entries.iter().map(Interval::from_config).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn zone(name: &str) -> Zone {
+        Zone::parse(name).unwrap()
+    }
+
+    #[test]
+    fn parses_date_bounds() {
+        let bounds = DateBounds::parse(Some("2024-05-01"), Some(" 2024-05-03 ")).unwrap();
+        assert_eq!(bounds, DateBounds { from: Some(date("2024-05-01")), to: Some(date("2024-05-03")) });
+        assert_eq!(DateBounds::parse(None, None).unwrap(), DateBounds::default());
+        assert!(DateBounds::parse(Some("2024-05-01"), Some("2024-05-01")).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_date_bounds() {
+        let err = DateBounds::parse(Some("05/01/2024"), None).unwrap_err().to_string();
+        assert!(err.contains("--from") && err.contains("YYYY-MM-DD"), "{}", err);
+        assert!(DateBounds::parse(None, Some("2024-02-30")).is_err());
+        let err = DateBounds::parse(Some("2024-05-03"), Some("2024-05-01")).unwrap_err().to_string();
+        assert_eq!(err, "--from 2024-05-03 is after --to 2024-05-01");
+    }
+
+    #[test]
+    fn resolves_bounds_to_whole_days() {
+        let bounds = DateBounds::parse(Some("2024-05-01"), Some("2024-05-03")).unwrap();
+        let range = TimeRange::resolve(&bounds, &Interval::rolling("24h", 24), zone("UTC"));
+        assert_eq!(range.git_since(), "2024-05-01T00:00:00+00:00");
+        assert_eq!(range.git_until(), "2024-05-03T23:59:59+00:00");
+        assert_eq!(range.label, "2024-05-01 to 2024-05-03");
+    }
+
+    #[test]
+    fn resolves_bounds_in_a_non_utc_zone() {
+        let bounds = DateBounds::parse(Some("2024-01-10"), Some("2024-07-10")).unwrap();
+        let range = TimeRange::resolve(&bounds, &Interval::rolling("24h", 24), zone("America/New_York"));
+        // Standard time at the start, daylight saving time at the end
+        assert_eq!(range.git_since(), "2024-01-10T00:00:00-05:00");
+        assert_eq!(range.git_until(), "2024-07-10T23:59:59-04:00");
+        assert_eq!((range.start_date().as_str(), range.end_date().as_str()), ("2024-01-10", "2024-07-10"));
+    }
+
+    #[test]
+    fn a_missing_from_falls_back_to_the_interval() {
+        let bounds = DateBounds::parse(None, Some("2024-05-03")).unwrap();
+        let range = TimeRange::resolve(&bounds, &Interval::rolling("48h", 48), zone("Europe/Berlin"));
+        assert_eq!(range.git_since(), "2024-05-01T23:59:59+02:00");
+        assert_eq!(range.git_until(), "2024-05-03T23:59:59+02:00");
+    }
+
+    #[test]
+    fn times_in_a_dst_gap_move_to_the_end_of_the_gap() {
+        // Clocks jump from 02:00 to 03:00
+        let berlin = zone("Europe/Berlin");
+        let gap = date("2024-03-31").and_hms_opt(2, 30, 15).unwrap();
+        assert_eq!(berlin.at(gap).to_rfc3339(), "2024-03-31T03:00:00+02:00");
+        // Chile skips midnight, so the day starts at 01:00
+        let santiago = zone("America/Santiago");
+        assert_eq!(santiago.start_of_day(date("2024-09-08")).to_rfc3339(), "2024-09-08T01:00:00-03:00");
+        // Repeated times take the first occurrence
+        let fall = date("2024-10-27").and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(berlin.at(fall).to_rfc3339(), "2024-10-27T02:30:00+02:00");
+    }
+
+    #[test]
+    fn calendar_periods_end_at_month_ends() {
+        // Leap year February, and a 31-day month before a 30-day one
+        assert_eq!(CalendarPeriod::LastMonth.dates(date("2024-03-31")), (date("2024-02-01"), date("2024-02-29")));
+        assert_eq!(CalendarPeriod::LastMonth.dates(date("2023-03-01")), (date("2023-02-01"), date("2023-02-28")));
+        assert_eq!(CalendarPeriod::ThisMonth.dates(date("2024-04-30")), (date("2024-04-01"), date("2024-04-30")));
+        assert_eq!(CalendarPeriod::LastMonth.dates(date("2024-01-15")), (date("2023-12-01"), date("2023-12-31")));
+    }
+
+    #[test]
+    fn calendar_weeks_cross_the_year_boundary() {
+        // Thursday, January 2nd 2025 is in ISO week 1, which starts on Monday, December 30th
+        assert_eq!(CalendarPeriod::ThisWeek.dates(date("2025-01-02")), (date("2024-12-30"), date("2025-01-02")));
+        assert_eq!(CalendarPeriod::LastWeek.dates(date("2025-01-02")), (date("2024-12-23"), date("2024-12-29")));
+        // A Monday starts its own week
+        assert_eq!(CalendarPeriod::ThisWeek.dates(date("2024-12-30")), (date("2024-12-30"), date("2024-12-30")));
+        assert_eq!(CalendarPeriod::Yesterday.dates(date("2025-01-01")), (date("2024-12-31"), date("2024-12-31")));
+    }
+
+    #[test]
+    fn calendar_quarters() {
+        assert_eq!(CalendarPeriod::ThisQuarter.dates(date("2024-05-20")), (date("2024-04-01"), date("2024-05-20")));
+        assert_eq!(CalendarPeriod::ThisQuarter.dates(date("2024-09-30")), (date("2024-07-01"), date("2024-09-30")));
+        assert_eq!(CalendarPeriod::LastQuarter.dates(date("2024-05-20")), (date("2024-01-01"), date("2024-03-31")));
+        assert_eq!(CalendarPeriod::LastQuarter.dates(date("2024-02-29")), (date("2023-10-01"), date("2023-12-31")));
+        assert_eq!(CalendarPeriod::parse(" This_Quarter"), Some(CalendarPeriod::ThisQuarter));
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use gemini_rs::types::{Schema, Type};
+use serde::{Deserialize, Serialize};
+
+/// A summary as data: per project, topics with bullets and one line per day.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StructuredSummary {
+    pub projects: Vec<ProjectSummary>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProjectSummary {
+    pub name: String,
+    #[serde(default)]
+    pub topics: Vec<TopicSummary>,
+    #[serde(default)]
+    pub days: Vec<DaySummary>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TopicSummary {
+    pub title: String,
+    #[serde(default)]
+    pub bullets: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DaySummary {
+    /// YYYY-MM-DD
+    pub date: String,
+    pub text: String,
+}
+
+/// Appended to the prompt. The schema also goes along as the response schema,
+/// but spelling it out keeps the fields' meaning close to the other rules.
+pub const INSTRUCTIONS: &str = r#"
+
+IMPORTANT: Answer with JSON only, matching this schema:
+{"projects": [{"name": "...", "topics": [{"title": "...", "bullets": ["..."]}], "days": [{"date": "YYYY-MM-DD", "text": "..."}]}]}
+- One entry in "projects" per project, in the order of the commit data
+- "topics" is the overall summary: a short title per topic with up to 4 bullets
+- "days" is the daily breakdown: one entry per day with changes, oldest first, "text" in a single line
+- Ticket numbers and Markdown inside the strings are fine; the language rules above still apply
+"#;
+
+fn string() -> Schema {
+    Schema { schema_type: Some(Type::String), ..Default::default() }
+}
+
+fn array(items: Schema) -> Schema {
+    Schema { schema_type: Some(Type::Array), items: Some(Box::new(items)), ..Default::default() }
+}
+
+/// An object whose properties are all required, in the given order.
+fn object(properties: Vec<(&str, Schema)>) -> Schema {
+    let names: Vec<String> = properties.iter().map(|(name, _)| name.to_string()).collect();
+    Schema {
+        schema_type: Some(Type::Object),
+        properties: Some(properties.into_iter().map(|(name, schema)| (name.to_string(), schema)).collect::<BTreeMap<_, _>>()),
+        required: Some(names.clone()),
+        property_ordering: Some(names),
+        ..Default::default()
+    }
+}
+
+/// The response schema for the provider's JSON mode.
+pub fn response_schema() -> Schema {
+    let topic = object(vec![("title", string()), ("bullets", array(string()))]);
+    let day = object(vec![("date", string()), ("text", string())]);
+    let project = object(vec![("name", string()), ("topics", array(topic)), ("days", array(day))]);
+    object(vec![("projects", array(project))])
+}
+
+/// Parses and validates a response. A Markdown code fence around the JSON is
+/// tolerated; anything else that does not match the schema is an error.
+pub fn parse(text: &str) -> Result<StructuredSummary> {
+    let json = text.trim();
+    let json = json.strip_prefix("```json").or_else(|| json.strip_prefix("```")).unwrap_or(json);
+    let json = json.strip_suffix("```").unwrap_or(json).trim();
+    let summary: StructuredSummary = serde_json::from_str(json).map_err(|e| anyhow!("not summary JSON: {}", e))?;
+    summary.validate()?;
+    Ok(summary)
+}
+
+impl StructuredSummary {
+    fn validate(&self) -> Result<()> {
+        if self.projects.is_empty() {
+            return Err(anyhow!("no projects"));
+        }
+        for (i, project) in self.projects.iter().enumerate() {
+            if project.name.trim().is_empty() {
+                return Err(anyhow!("projects[{}].name is empty", i));
+            }
+            if project.topics.is_empty() && project.days.is_empty() {
+                return Err(anyhow!("projects[{}] has neither topics nor days", i));
+            }
+            for (j, topic) in project.topics.iter().enumerate() {
+                if topic.title.trim().is_empty() {
+                    return Err(anyhow!("projects[{}].topics[{}].title is empty", i, j));
+                }
+                if topic.bullets.iter().any(|b| b.trim().is_empty()) {
+                    return Err(anyhow!("projects[{}].topics[{}] has an empty bullet", i, j));
+                }
+            }
+            for (j, day) in project.days.iter().enumerate() {
+                if NaiveDate::parse_from_str(day.date.trim(), "%Y-%m-%d").is_err() {
+                    return Err(anyhow!("projects[{}].days[{}].date '{}' is not YYYY-MM-DD", i, j, day.date));
+                }
+                if day.text.trim().is_empty() {
+                    return Err(anyhow!("projects[{}].days[{}].text is empty", i, j));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Markdown in the shape of the default prompt's output. It is the summary
+    /// text for copying, exports, the journal and publishing.
+    pub fn to_markdown(&self) -> String {
+        self.projects
+            .iter()
+            .map(|project| {
+                let mut out = format!("## {}\n", project.name.trim());
+                if !project.topics.is_empty() {
+                    out.push_str("\n*Overall summary*:\n");
+                    for topic in &project.topics {
+                        out.push_str(&format!("- *{}*\n", topic.title.trim()));
+                        for bullet in &topic.bullets {
+                            out.push_str(&format!("    - {}\n", bullet.trim()));
+                        }
+                    }
+                }
+                if !project.days.is_empty() {
+                    out.push_str("\nDaily breakdown:\n");
+                    for day in &project.days {
+                        out.push_str(&format!("- *{}*: {}\n", day.date.trim(), day.text.trim()));
+                    }
+                }
+                out.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"{"projects": [
+        {"name": "web", "topics": [{"title": "Login", "bullets": ["Added SSO", "Fixed logout"]}],
+         "days": [{"date": "2024-05-01", "text": "Login work"}, {"date": "2024-05-02", "text": "Bug fixes"}]},
+        {"name": "api", "days": [{"date": "2024-05-02", "text": "Rate limits"}]}
+    ]}"#;
+
+    fn error(text: &str) -> String {
+        parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_a_valid_summary() {
+        let summary = parse(VALID).unwrap();
+        assert_eq!(summary.projects.len(), 2);
+        assert_eq!(summary.projects[0].topics[0].bullets, vec!["Added SSO", "Fixed logout"]);
+        assert!(summary.projects[1].topics.is_empty());
+    }
+
+    #[test]
+    fn tolerates_a_code_fence() {
+        assert!(parse(&format!("```json\n{}\n```", VALID)).is_ok());
+        assert!(parse(&format!("  ```\n{}\n```\n", VALID)).is_ok());
+    }
+
+    #[test]
+    fn rejects_what_is_not_summary_json() {
+        assert!(error("Here is your summary: ...").starts_with("not summary JSON"));
+        assert!(error(r#"{"summary": "text"}"#).starts_with("not summary JSON"));
+    }
+
+    #[test]
+    fn rejects_each_invalid_field() {
+        let cases = [
+            (r#"{"projects": []}"#, "no projects"),
+            (r#"{"projects": [{"name": " ", "days": [{"date": "2024-05-01", "text": "x"}]}]}"#, "projects[0].name is empty"),
+            (r#"{"projects": [{"name": "web"}]}"#, "projects[0] has neither topics nor days"),
+            (r#"{"projects": [{"name": "web", "topics": [{"title": "", "bullets": []}]}]}"#, "projects[0].topics[0].title is empty"),
+            (r#"{"projects": [{"name": "web", "topics": [{"title": "T", "bullets": ["a", " "]}]}]}"#, "projects[0].topics[0] has an empty bullet"),
+            (r#"{"projects": [{"name": "web", "days": [{"date": "May 1", "text": "x"}]}]}"#, "projects[0].days[0].date 'May 1' is not YYYY-MM-DD"),
+            (r#"{"projects": [{"name": "web", "days": [{"date": "2024-05-01", "text": ""}]}]}"#, "projects[0].days[0].text is empty"),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(error(json), expected);
+        }
+    }
+
+    #[test]
+    fn renders_markdown_in_the_default_prompt_shape() {
+        assert_eq!(
+            parse(VALID).unwrap().to_markdown(),
+            "## web\n\n*Overall summary*:\n- *Login*\n    - Added SSO\n    - Fixed logout\n\n\
+             Daily breakdown:\n- *2024-05-01*: Login work\n- *2024-05-02*: Bug fixes\n\n---\n\n\
+             ## api\n\nDaily breakdown:\n- *2024-05-02*: Rate limits"
+        );
+    }
+
+    #[test]
+    fn the_schema_requires_every_field() {
+        let schema = response_schema();
+        assert_eq!(schema.required, Some(vec!["projects".to_string()]));
+        let project = schema.properties.unwrap()["projects"].items.clone().unwrap();
+        assert_eq!(project.property_ordering, Some(vec!["name".to_string(), "topics".to_string(), "days".to_string()]));
+    }
+}
@@ -0,0 +1,266 @@
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use crate::config::TeamMember;
+use crate::git::reload_commits;
+use crate::prompts::{build_prompt, prompt_team, PromptVars};
+use crate::offline::offline_summary;
+use crate::range::TimeRange;
+use crate::redact::Redactor;
+use crate::utils::{format_commits_for_prompt, CommitData};
+
+/// How a team report is put together.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TeamReportMode {
+    /// One structured prompt covering every member.
+    Combined,
+    /// One summary per member, joined into a single report.
+    PerPerson,
+}
+
+impl TeamReportMode {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().replace('-', "_").as_str() {
+            "" | "combined" => Ok(TeamReportMode::Combined),
+            "per_person" => Ok(TeamReportMode::PerPerson),
+            other => Err(anyhow!("Unknown team_report '{}'. Use \"combined\" or \"per_person\".", other)),
+        }
+    }
+}
+
+/// Commits of one team member, gathered across all repos.
+#[derive(Clone, Debug)]
+pub struct MemberCommits {
+    pub member: TeamMember,
+    pub commits: CommitData,
+}
+
+/// Rejects members without emails, since an empty author filter would match everyone's commits.
+pub fn check_members(team: &[TeamMember]) -> Result<()> {
+    match team.iter().find(|m| m.emails.iter().all(|e| e.trim().is_empty())) {
+        Some(member) => Err(anyhow!("Team member '{}' has no emails. Add at least one to its [[team]] entry.", member.name)),
+        None => Ok(()),
+    }
+}
+
+/// Collects each member's commits via their email aliases.
+pub fn collect_team_commits(repos: &Vec<PathBuf>, range: &TimeRange, detailed: bool, team: &[TeamMember]) -> Result<Vec<MemberCommits>> {
+    team.iter()
+        .map(|member| {
+            Ok(MemberCommits {
+                member: member.clone(),
+                commits: reload_commits(repos, range, false, detailed, &member.emails)?,
+            })
+        })
+        .collect()
+}
+
+/// Everything needed to turn team commits into a report.
+#[derive(Clone, Debug)]
+pub struct TeamReport {
+    pub mode: TeamReportMode,
+    pub from: String,
+    pub to: String,
+    pub interval: String,
+    pub lang: String,
+    pub model: String,
+    pub prompt_path: Option<String>,
+    /// Response cache TTL, and whether to bypass it.
+    pub cache_hours: u64,
+    pub refresh: bool,
+    pub redactor: Redactor,
+    /// One offline summary per member instead of asking the AI.
+    pub offline: bool,
+}
+
+impl TeamReport {
+    /// Generates the combined Markdown report. API errors end up in the text, as with single summaries.
+    pub async fn generate(&self, members: &[MemberCommits]) -> String {
+        let title = format!("# Team report: {} – {}", self.from, self.to);
+        if self.offline {
+            return match self.mode {
+                TeamReportMode::Combined => {
+                    let names: Vec<String> = members.iter().map(|m| m.member.name.clone()).collect();
+                    let commits = merge_by_repo(members);
+                    let lines = format_commits_for_prompt(&commits);
+                    format!("{}\n\n{}", title, offline_summary(&self.vars("Team", &names, &lines, &commits)))
+                }
+                TeamReportMode::PerPerson => {
+                    let sections = members.iter().map(|m| {
+                        let body = if m.commits.is_empty() {
+                            "_No commits in this period._".to_string()
+                        } else {
+                            offline_summary(&self.vars(&m.member.name, &[], &format_commits_for_prompt(&m.commits), &m.commits))
+                        };
+                        format!("## {}\n\n{}", m.member.name, body)
+                    });
+                    std::iter::once(title).chain(sections).collect::<Vec<_>>().join("\n\n---\n\n")
+                }
+            };
+        }
+        match self.mode {
+            TeamReportMode::Combined => {
+                let names: Vec<String> = members.iter().map(|m| m.member.name.clone()).collect();
+                let commits = members.iter()
+                    .map(|m| {
+                        let lines = format_commits_for_prompt(&m.commits);
+                        format!("### {}\n{}", m.member.name, if lines.is_empty() { "(no commits)".to_string() } else { lines })
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                let all_commits: CommitData = members.iter().flat_map(|m| m.commits.clone()).collect();
+                // Masked before the prompt is built around them
+                let commits = self.redactor.redact(&commits, &repo_names(&all_commits)).text;
+                let prompt = prompt_team(&self.from, &self.to, &self.lang, &names, &commits);
+                format!("{}\n\n{}", title, self.fetch(&prompt).await)
+            }
+            TeamReportMode::PerPerson => {
+                let mut sections = vec![title];
+                for m in members {
+                    let body = if m.commits.is_empty() {
+                        "_No commits in this period._".to_string()
+                    } else {
+                        let commits = format_commits_for_prompt(&m.commits);
+                        let vars = self.vars(&m.member.name, &[], &commits, &m.commits);
+                        let redacted = self.redactor.redact_vars(&vars, &repo_names(&m.commits));
+                        match build_prompt(self.prompt_path.as_deref(), &redacted.vars(&vars)) {
+                            Ok(prompt) => self.fetch(&prompt).await,
+                            Err(e) => e.to_string(),
+                        }
+                    };
+                    sections.push(format!("## {}\n\n{}", m.member.name, body));
+                }
+                sections.join("\n\n---\n\n")
+            }
+        }
+    }
+
+    /// Model recorded with the report: "offline" when no AI was asked.
+    pub fn provider_model(&self) -> &str {
+        if self.offline { "offline" } else { &self.model }
+    }
+
+    /// Prompt values for a section about `project` (a member's name, or the
+    /// whole team); `commits` is the formatted `commit_data`.
+    fn vars<'a>(&'a self, project: &'a str, authors: &'a [String], commits: &'a str, commit_data: &'a CommitData) -> PromptVars<'a> {
+        PromptVars {
+            from: &self.from,
+            to: &self.to,
+            project,
+            interval: &self.interval,
+            lang: &self.lang,
+            commits,
+            authors,
+            ticket_details: "",
+            forge_activity: "",
+            plans: "",
+            blockers: "",
+            commit_data,
+            filter_by_user: false,
+            tickets: &[],
+        }
+    }
+
+    /// Sends `prompt`, whose commits are already masked.
+    async fn fetch(&self, prompt: &str) -> String {
+        match crate::network::fetch_summary_cached(prompt, &self.lang, &self.model, self.cache_hours, self.refresh, false).await {
+            Ok((s, _)) => s,
+            Err(e) => format!("Gemini error: {}", e),
+        }
+    }
+}
+
+/// Directory names of the repos `commits` come from, for never-send terms.
+fn repo_names(commits: &CommitData) -> Vec<String> {
+    commits.iter().map(|(repo, _)| repo.file_name().unwrap_or_default().to_string_lossy().to_string()).collect()
+}
+
+/// Every member's commits, one entry per repo.
+fn merge_by_repo(members: &[MemberCommits]) -> CommitData {
+    let mut merged: CommitData = vec![];
+    for (repo, lines) in members.iter().flat_map(|m| m.commits.iter()) {
+        match merged.iter_mut().find(|(r, _)| r == repo) {
+            Some((_, all)) => all.extend(lines.iter().cloned()),
+            None => merged.push((repo.clone(), lines.clone())),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(mode: TeamReportMode) -> TeamReport {
+        TeamReport {
+            mode,
+            from: "2024-05-01".to_string(),
+            to: "2024-05-02".to_string(),
+            interval: "48h".to_string(),
+            lang: "en".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            prompt_path: None,
+            cache_hours: 0,
+            refresh: false,
+            redactor: Redactor::default(),
+            offline: true,
+        }
+    }
+
+    fn member(name: &str, lines: &[&str]) -> MemberCommits {
+        MemberCommits {
+            member: TeamMember { name: name.to_string(), emails: vec![] },
+            commits: match lines {
+                [] => vec![],
+                lines => vec![(PathBuf::from("/src/web"), lines.iter().map(|l| l.to_string()).collect())],
+            },
+        }
+    }
+
+    #[test]
+    fn members_need_an_email() {
+        let alice = TeamMember { name: "Alice".to_string(), emails: vec!["alice@example.com".to_string()] };
+        let bob = TeamMember { name: "Bob".to_string(), emails: vec![" ".to_string()] };
+        assert!(check_members(std::slice::from_ref(&alice)).is_ok());
+        let err = check_members(&[alice, bob]).unwrap_err().to_string();
+        assert!(err.contains("'Bob'"), "{}", err);
+        assert!(check_members(&[TeamMember { name: "Carol".to_string(), emails: vec![] }]).is_err());
+    }
+
+    fn members() -> Vec<MemberCommits> {
+        vec![
+            member("Ann", &["a1b2c3d|2024-05-01 10:00|Ann|feat: add login"]),
+            member("Bob", &["e4f5a6b|2024-05-02 11:00|Bob|fix: crash on logout"]),
+            member("Cid", &[]),
+        ]
+    }
+
+    fn generate(report: &TeamReport) -> String {
+        tokio::runtime::Runtime::new().unwrap().block_on(report.generate(&members()))
+    }
+
+    #[test]
+    fn offline_combined_report_is_one_summary() {
+        let text = generate(&report(TeamReportMode::Combined));
+        assert!(text.starts_with("# Team report: 2024-05-01 – 2024-05-02\n\n## Team"), "{}", text);
+        assert!(text.contains("add login") && text.contains("crash on logout"), "{}", text);
+        assert!(!text.contains("## Ann") && !text.contains("## Cid"), "{}", text);
+    }
+
+    #[test]
+    fn offline_per_person_report_has_a_section_per_member() {
+        let text = generate(&report(TeamReportMode::PerPerson));
+        let sections: Vec<&str> = text.split("\n\n---\n\n").collect();
+        assert_eq!(sections.len(), 4, "{}", text);
+        assert!(sections[1].starts_with("## Ann") && sections[1].contains("add login") && !sections[1].contains("logout"));
+        assert!(sections[2].starts_with("## Bob") && sections[2].contains("crash on logout"));
+        assert_eq!(sections[3], "## Cid\n\n_No commits in this period._");
+    }
+
+    #[test]
+    fn records_the_provider_that_wrote_the_report() {
+        let mut report = report(TeamReportMode::Combined);
+        assert_eq!(report.provider_model(), "offline");
+        report.offline = false;
+        assert_eq!(report.provider_model(), "gemini-2.5-flash");
+    }
+}
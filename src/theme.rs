@@ -16,6 +16,7 @@ pub struct Theme {
     pub commit_datetime: Style,
     pub commit_author: Style,
     pub commit_ticket: Style,
+    pub search_match: Style,
     pub repo_path: Style,
     pub repo_commit_count: Style,
     pub footer: Style,
@@ -41,6 +42,7 @@ impl Default for Theme {
             commit_datetime: Style::default().fg(Color::Magenta),
             commit_author: Style::default().fg(Color::Green),
             commit_ticket: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            search_match: Style::default().fg(Color::Black).bg(Color::Yellow),
             repo_path: Style::default().fg(Color::Cyan),
             repo_commit_count: Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
             footer: Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::config::TrackerConfig;
+use crate::utils::{extract_tickets, CommitData};
+
+/// Title and status of one ticket, as cached on disk.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TicketInfo {
+    pub key: String,
+    pub title: String,
+    pub status: String,
+    pub url: Option<String>,
+    /// Unix timestamp of the lookup.
+    pub fetched_at: i64,
+}
+
+/// Source of ticket details. Implement this to support another tracker.
+pub trait IssueTracker: Send + Sync {
+    fn fetch<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<TicketInfo>>;
+}
+
+/// Jira REST API v2. Jira Cloud uses `user` (email) plus an API token;
+/// without `user`, the token is sent as a bearer token (Jira Server PATs).
+pub struct JiraTracker {
+    base_url: String,
+    user: Option<String>,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl IssueTracker for JiraTracker {
+    fn fetch<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<TicketInfo>> {
+        Box::pin(async move {
+            let url = format!("{}/rest/api/2/issue/{}?fields=summary,status", self.base_url, key);
+            let request = match &self.user {
+                Some(user) => self.client.get(&url).basic_auth(user, Some(&self.token)),
+                None => self.client.get(&url).bearer_auth(&self.token),
+            };
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("Jira returned {} for {}", response.status(), key));
+            }
+            let issue: Value = response.json().await?;
+            Ok(TicketInfo {
+                key: key.to_string(),
+                title: issue["fields"]["summary"].as_str().unwrap_or_default().to_string(),
+                status: issue["fields"]["status"]["name"].as_str().unwrap_or_default().to_string(),
+                url: Some(format!("{}/browse/{}", self.base_url, key)),
+                fetched_at: chrono::Utc::now().timestamp(),
+            })
+        })
+    }
+}
+
+/// Builds the tracker configured in `[tracker]`.
+pub fn tracker_from_config(config: &TrackerConfig) -> Result<Box<dyn IssueTracker>> {
+    let expand = |value: &str| -> Result<String> {
+        Ok(shellexpand::env(value).map_err(|e| anyhow!("[tracker]: {}", e))?.into_owned())
+    };
+    match config.kind.trim().to_lowercase().as_str() {
+        "jira" => Ok(Box::new(JiraTracker {
+            base_url: expand(&config.url)?.trim_end_matches('/').to_string(),
+            user: config.user.as_deref().map(expand).transpose()?,
+            token: expand(&config.token)?,
+            client: reqwest::Client::new(),
+        })),
+        other => Err(anyhow!("Unknown tracker kind '{}'. Supported: jira.", other)),
+    }
+}
+
+/// Ticket lookups, persisted in the user's cache directory.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TicketCache {
+    pub entries: HashMap<String, TicketInfo>,
+}
+
+impl TicketCache {
+    fn path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("whid").join("tickets.json"))
+    }
+
+    /// Loads the cache; a missing or unreadable file gives an empty cache.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No cache directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Keys that are not cached or older than `max_age_hours`.
+    pub fn stale(&self, keys: &[String], max_age_hours: u64) -> Vec<String> {
+        let oldest = chrono::Utc::now().timestamp() - (max_age_hours * 3600) as i64;
+        keys.iter()
+            .filter(|key| self.entries.get(*key).is_none_or(|info| info.fetched_at < oldest))
+            .cloned()
+            .collect()
+    }
+
+    /// The `{ticket_details}` prompt block: one line per known ticket.
+    pub fn details(&self, keys: &[String]) -> String {
+        self.infos(keys)
+            .iter()
+            .map(|info| format!("- {}: {} [{}]", info.key, info.title, info.status))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The cached tickets among `keys`, in their order.
+    pub fn infos(&self, keys: &[String]) -> Vec<TicketInfo> {
+        keys.iter().filter_map(|key| self.entries.get(key)).cloned().collect()
+    }
+}
+
+/// Tickets mentioned in the commits, limited to `projects` when that is non-empty.
+pub fn tickets_in(commits: &CommitData, projects: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    commits.iter()
+        .flat_map(|(_, lines)| lines.iter())
+        .flat_map(|line| extract_tickets(line))
+        .filter(|key| projects.is_empty() || projects.iter().any(|p| key.split('-').next() == Some(p.as_str())))
+        .filter(|key| seen.insert(key.clone()))
+        .collect()
+}
+
+/// Looks up stale tickets and stores the results. Failed lookups are skipped
+/// so that one missing ticket does not hide the others.
+pub async fn refresh(tracker: &dyn IssueTracker, cache: &Arc<Mutex<TicketCache>>, keys: &[String], max_age_hours: u64) {
+    let stale = cache.lock().unwrap().stale(keys, max_age_hours);
+    if stale.is_empty() {
+        return;
+    }
+    let mut fetched = false;
+    for key in &stale {
+        if let Ok(info) = tracker.fetch(key).await {
+            cache.lock().unwrap().entries.insert(key.clone(), info);
+            fetched = true;
+        }
+    }
+    if fetched {
+        let _ = cache.lock().unwrap().save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(key: &str, hours_ago: i64) -> TicketInfo {
+        TicketInfo {
+            key: key.to_string(),
+            title: format!("Title of {}", key),
+            status: "In Progress".to_string(),
+            url: None,
+            fetched_at: chrono::Utc::now().timestamp() - hours_ago * 3600,
+        }
+    }
+
+    fn cache(infos: Vec<TicketInfo>) -> TicketCache {
+        TicketCache { entries: infos.into_iter().map(|i| (i.key.clone(), i)).collect() }
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn missing_and_old_tickets_are_stale() {
+        let cache = cache(vec![info("CPT-1", 1), info("CPT-2", 30)]);
+        assert_eq!(cache.stale(&keys(&["CPT-1", "CPT-2", "CPT-3"]), 24), keys(&["CPT-2", "CPT-3"]));
+        assert_eq!(cache.stale(&keys(&["CPT-1", "CPT-2"]), 48), Vec::<String>::new());
+        // A max age of zero refreshes everything fetched before this second
+        assert_eq!(cache.stale(&keys(&["CPT-1"]), 0), keys(&["CPT-1"]));
+    }
+
+    #[test]
+    fn details_follow_the_requested_order() {
+        let cache = cache(vec![info("CPT-1", 0), info("DSG-7", 0)]);
+        assert_eq!(
+            cache.details(&keys(&["DSG-7", "CPT-9", "CPT-1"])),
+            "- DSG-7: Title of DSG-7 [In Progress]\n- CPT-1: Title of CPT-1 [In Progress]"
+        );
+    }
+
+    #[test]
+    fn finds_each_ticket_once_in_configured_projects() {
+        let commits: CommitData = vec![(PathBuf::from("/src/web"), vec![
+            "abc1234|2024-05-01 10:00|CPT-12 fix login, see DSG-3".to_string(),
+            "def5678|2024-05-02 09:30|CPT-12 follow-up".to_string(),
+        ])];
+        assert_eq!(tickets_in(&commits, &[]), keys(&["CPT-12", "DSG-3"]));
+        assert_eq!(tickets_in(&commits, &keys(&["DSG"])), keys(&["DSG-3"]));
+    }
+}
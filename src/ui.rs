@@ -8,22 +8,48 @@ use ratatui::{
 };
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use crate::models::{FocusArea, PopupQuote};
+use crate::models::{AuthorPicker, DateRangeDialog, ExportDialog, FocusArea, HistoryView, NotesEditor, NotesField, PopupQuote, PresetPicker, PublishDialog, SearchState, SectionId, SummarySections};
+use crate::presets::Preset;
+use similar::ChangeTag;
+use crate::config::PublishTarget;
+use chrono::Datelike;
 use crate::git::get_commit_details;
 use crate::models::SelectedCommits;
+use crate::markdown::render_markdown;
 use crate::CommitTab;
-use once_cell::sync::Lazy;
+use crate::utils::{extract_tickets, TICKET_REGEX};
+use crate::tickets::{TicketCache, TicketInfo};
+use crate::forge::{ActivityKind, ForgeActivity};
+use std::collections::HashMap;
 use regex::Regex;
 use crate::theme::Theme;
+use crate::range::TimeRange;
+use crate::export::export_target;
 
 // Type alias for commit data for clarity
 pub type CommitData = Vec<(PathBuf, Vec<String>)>;
 
-// Compile the ticket regex once for all uses
-static TICKET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Z]+-\d+").unwrap());
+/// Pushes `text` with `style`, highlighting search matches on top of it.
+fn push_highlighted(spans: &mut Vec<Span<'static>>, text: &str, style: Style, search: Option<&Regex>, theme: &Theme) {
+    let Some(re) = search else {
+        spans.push(Span::styled(text.to_owned(), style));
+        return;
+    };
+    let mut last = 0;
+    for m in re.find_iter(text).filter(|m| !m.is_empty()) {
+        if m.start() > last {
+            spans.push(Span::styled(text[last..m.start()].to_owned(), style));
+        }
+        spans.push(Span::styled(text[m.start()..m.end()].to_owned(), style.patch(theme.search_match)));
+        last = m.end();
+    }
+    if last < text.len() {
+        spans.push(Span::styled(text[last..].to_owned(), style));
+    }
+}
 
-/// Renders a commit line with syntax highlighting and ticket detection.
-fn render_commit_line<'a>(commit: &'a str, indicator: String, filter_by_user: bool, theme: &Theme) -> Line<'a> {
+/// Renders a commit line with syntax highlighting, ticket detection and search matches.
+fn render_commit_line<'a>(commit: &'a str, indicator: String, filter_by_user: bool, theme: &Theme, search: Option<&Regex>, tickets: &HashMap<String, TicketInfo>) -> Line<'a> {
     let mut spans = vec![];
     let parts: Vec<&str> = if filter_by_user {
         commit.splitn(3, '|').collect()
@@ -31,8 +57,8 @@ fn render_commit_line<'a>(commit: &'a str, indicator: String, filter_by_user: bo
         commit.splitn(4, '|').collect()
     };
 
-    if let Some(hash) = parts.get(0) {
-        spans.push(Span::styled(hash.trim().to_owned(), theme.commit_hash));
+    if let Some(hash) = parts.first() {
+        push_highlighted(&mut spans, hash.trim(), theme.commit_hash, search, theme);
         spans.push(Span::raw(" | "));
     }
 
@@ -41,39 +67,33 @@ fn render_commit_line<'a>(commit: &'a str, indicator: String, filter_by_user: bo
         spans.push(Span::raw(" | "));
     }
 
-    if filter_by_user {
-        if let Some(subject_str) = parts.get(2) {
-            let subject = subject_str.trim();
-            let mut last = 0;
-            for m in TICKET_REGEX.find_iter(subject) {
-                if m.start() > last {
-                    spans.push(Span::raw(subject[last..m.start()].to_owned()));
-                }
-                spans.push(Span::styled(subject[m.start()..m.end()].to_owned(), theme.commit_ticket));
-                last = m.end();
-            }
-            if last < subject.len() {
-                spans.push(Span::raw(subject[last..].to_owned()));
-            }
-        }
+    let subject_index = if filter_by_user {
+        2
     } else {
         if let Some(author) = parts.get(2) {
-            spans.push(Span::styled(author.trim().to_owned(), theme.commit_author));
+            push_highlighted(&mut spans, author.trim(), theme.commit_author, search, theme);
             spans.push(Span::raw(" | "));
         }
-        
-        if let Some(subject_str) = parts.get(3) {
-            let subject = subject_str.trim();
-            let mut last = 0;
-            for m in TICKET_REGEX.find_iter(subject) {
-                if m.start() > last {
-                    spans.push(Span::raw(subject[last..m.start()].to_owned()));
-                }
-                spans.push(Span::styled(subject[m.start()..m.end()].to_owned(), theme.commit_ticket));
-                last = m.end();
+        3
+    };
+
+    if let Some(subject_str) = parts.get(subject_index) {
+        let subject = subject_str.trim();
+        let mut last = 0;
+        for m in TICKET_REGEX.find_iter(subject) {
+            if m.start() > last {
+                push_highlighted(&mut spans, &subject[last..m.start()], Style::default(), search, theme);
             }
-            if last < subject.len() {
-                spans.push(Span::raw(subject[last..].to_owned()));
+            push_highlighted(&mut spans, &subject[m.start()..m.end()], theme.commit_ticket, search, theme);
+            last = m.end();
+        }
+        if last < subject.len() {
+            push_highlighted(&mut spans, &subject[last..], Style::default(), search, theme);
+        }
+        // Ticket titles from the issue tracker, once looked up
+        for key in extract_tickets(subject) {
+            if let Some(info) = tickets.get(&key) {
+                spans.push(Span::styled(format!("  · {}", info.title), Style::default().fg(theme.text_secondary)));
             }
         }
     }
@@ -84,15 +104,14 @@ fn render_commit_line<'a>(commit: &'a str, indicator: String, filter_by_user: bo
 }
 
 /// Renders the commits view.
+#[allow(clippy::too_many_arguments)]
 pub fn render_commits(
     f: &mut Frame,
     theme: &Theme,
     _repos: &Vec<PathBuf>,
     selected_repo_index: usize,
     data: &CommitData,
-    interval_label: &str,
-    from_date: &Option<String>,
-    to_date: &Option<String>,
+    time_range: &TimeRange,
     selected_commit_index: Option<usize>,
     show_details: bool,
     focus: FocusArea,
@@ -104,13 +123,21 @@ pub fn render_commits(
     selected_commits: Option<&Arc<Mutex<SelectedCommits>>>,
     selected_tab: CommitTab,
     detailed_commit_view: bool,
+    range_dialog: &DateRangeDialog,
+    search: &SearchState,
+    author_picker: &AuthorPicker,
+    export_dialog: &ExportDialog,
+    publish_dialog: &PublishDialog,
+    publish_targets: &[PublishTarget],
+    ticket_cache: &Arc<Mutex<TicketCache>>,
+    forge_activity: Option<&Arc<Mutex<ForgeActivity>>>,
+    notes_editor: &NotesEditor,
+    history: &HistoryView,
+    preset_picker: &PresetPicker,
+    presets: &[Preset],
 ) {
-    let display_interval = if let (Some(from), to) = (from_date, to_date) {
-        let to_str = to.as_deref().unwrap_or("today");
-        format!("{} to {}", from, to_str)
-    } else {
-        interval_label.to_string()
-    };
+    let display_interval = time_range.label.clone();
+    let tickets = ticket_cache.lock().unwrap().entries.clone();
 
     f.render_widget(Block::default().style(Style::default().bg(theme.root_bg)), f.area());
 
@@ -121,7 +148,7 @@ pub fn render_commits(
         .constraints([Constraint::Min(1), Constraint::Length(3)]).split(area);
 
     // Determine if we should dim the background
-    let dim_bg = popup_quote.map_or(false, |arc| arc.lock().unwrap().visible);
+    let dim_bg = popup_quote.is_some_and(|arc| arc.lock().unwrap().visible);
     let bg_fg = if dim_bg { theme.blurred_border } else { theme.text };
     let bg_cyan = if dim_bg { theme.blurred_border } else { theme.focus_border };
     let bg_magenta = if dim_bg { theme.blurred_border } else { Color::Magenta }; // Not in theme yet
@@ -153,14 +180,21 @@ pub fn render_commits(
     let commit_area = columns[1];
     let detail_area = if columns.len() > 2 { Some(columns[2]) } else { None };
 
-    // Split sidebar area into sidebar and button box
+    // Split sidebar area into repo list and, with a forge configured, PR activity
+    let forge_activity = forge_activity.map(|arc| arc.lock().unwrap());
+    let forge_height = forge_activity.as_ref()
+        .map(|a| (a.items.len() + a.errors.len()).clamp(1, 8) as u16 + 3)
+        .unwrap_or(0);
     let sidebar_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(2), // sidebar list
-            // Removed button box area
+            Constraint::Length(forge_height),
         ])
         .split(sidebar_area);
+    if let Some(activity) = &forge_activity {
+        render_forge_activity(f, theme, activity, sidebar_chunks[1], bg_cyan);
+    }
 
     // Sidebar list (only repos with commits in the current timeframe)
     let filtered_repos: Vec<&PathBuf> = data.iter().map(|(repo,_)| repo).collect();
@@ -268,12 +302,19 @@ pub fn render_commits(
     };
 
     // Header
+    let scope = if filter_by_user {
+        " (only mine)".to_string()
+    } else if !author_picker.selected.is_empty() {
+        let n = author_picker.selected.len();
+        format!(" ({} author{})", n, if n == 1 { "" } else { "s" })
+    } else {
+        String::new()
+    };
     let header = if selected_repo_index==usize::MAX {
-        if filter_by_user { format!("Standup Commits (only mine) – {}", display_interval) }
-        else { format!("Standup Commits – {}", display_interval) }
+        format!("Standup Commits{} – {}", scope, display_interval)
     } else if let Some((repo,_)) = data.get(selected_repo_index) {
         let name = repo.file_name().unwrap_or_default().to_string_lossy();
-        if filter_by_user { format!("{} (only mine) – {}", name, display_interval)} else {format!("{} – {}", name, display_interval)}
+        format!("{}{} – {}", name, scope, display_interval)
     } else { format!("Standup Commits – {}", display_interval) };
     let _header_style = Style::default().fg(bg_fg);
 
@@ -298,7 +339,7 @@ pub fn render_commits(
                             let mut detail_parts = commit.splitn(2, '\n');
                             let commit_line = detail_parts.next().unwrap_or("");
                             let body = detail_parts.next().unwrap_or("");
-                            let rendered_line = render_commit_line(commit_line, indicator, filter_by_user, theme);
+                            let rendered_line = render_commit_line(commit_line, indicator, filter_by_user, theme, search.regex.as_ref(), &tickets);
                             let item = ListItem::new(rendered_line).style(style).bg(theme.selection_bg);
                             items.push(item);
                             for line in body.lines() {
@@ -306,11 +347,11 @@ pub fn render_commits(
                             }
                         } else {
                             let commit_line = if detailed_commit_view {
-                                commit.splitn(2, '\n').next().unwrap_or("")
+                                commit.split('\n').next().unwrap_or("")
                             } else {
                                 commit
                             };
-                            let rendered_line = render_commit_line(commit_line, indicator, filter_by_user, theme);
+                            let rendered_line = render_commit_line(commit_line, indicator, filter_by_user, theme, search.regex.as_ref(), &tickets);
                             let mut item = ListItem::new(rendered_line).style(style);
                             if sel {
                                 item = item.bg(theme.selection_bg);
@@ -336,7 +377,7 @@ pub fn render_commits(
                     let star = if let Some(hash) = commit.split_whitespace().next() { if selected_set.contains(hash) {"*"} else {" "} } else {" "};
                     let indicator = format!("{}{}", star, if sel {"→"} else {"  " });
                     let style = if sel {Style::default().fg(theme.selection_fg).add_modifier(Modifier::BOLD)} else {Style::default().fg(bg_fg)};
-                    let rendered_line = render_commit_line(commit, indicator, filter_by_user, theme);
+                    let rendered_line = render_commit_line(commit, indicator, filter_by_user, theme, search.regex.as_ref(), &tickets);
                     let mut item = ListItem::new(rendered_line).style(style);
                     if sel {
                         item = item.bg(theme.selection_bg);
@@ -395,7 +436,7 @@ pub fn render_commits(
                             let star = if let Some(hash) = commit.split_whitespace().next() { if sel.set.contains(hash) {"*"} else {" "} } else {" "};
                             let indicator = format!("{}  ", star);
                             let style = Style::default().fg(theme.selection_fg).add_modifier(Modifier::BOLD);
-                            let line = render_commit_line(commit, indicator, filter_by_user, theme);
+                            let line = render_commit_line(commit, indicator, filter_by_user, theme, search.regex.as_ref(), &tickets);
                             items.push(ListItem::new(line).style(style));
                         }
                     }
@@ -435,100 +476,120 @@ pub fn render_commits(
     }
 
     // Unified detail view rendering on the right when toggled
-    if let Some(detail_chunk) = detail_area {
-        if show_details {
-            if let Some(sel_idx) = selected_commit_index {
-                let (repo_path, commit_line) = {
-                    if selected_repo_index == usize::MAX {
-                        let mut offset = 0;
-                        let mut found: Option<(PathBuf, String)> = None;
-                        for (repo, repo_commits) in data {
-                            if sel_idx < offset + repo_commits.len() {
-                                found = Some((repo.clone(), repo_commits.get(sel_idx - offset).cloned().unwrap_or_default()));
-                                break;
-                            }
-                            offset += repo_commits.len();
-                        }
-                        found.unwrap_or_else(|| {
-                            if let Some((r, commits_vec)) = data.first() {
-                                (r.clone(), commits_vec.first().cloned().unwrap_or_default())
-                            } else {
-                                (PathBuf::new(), String::new())
-                            }
-                        })
-                    } else if let Some((r, commits_vec)) = data.get(selected_repo_index) {
-                        (r.clone(), commits_vec.get(sel_idx).cloned().unwrap_or_default())
+    if let Some(detail_chunk) = detail_area && show_details && let Some(sel_idx) = selected_commit_index {
+        let (repo_path, commit_line) = {
+            if selected_repo_index == usize::MAX {
+                let mut offset = 0;
+                let mut found: Option<(PathBuf, String)> = None;
+                for (repo, repo_commits) in data {
+                    if sel_idx < offset + repo_commits.len() {
+                        found = Some((repo.clone(), repo_commits.get(sel_idx - offset).cloned().unwrap_or_default()));
+                        break;
+                    }
+                    offset += repo_commits.len();
+                }
+                found.unwrap_or_else(|| {
+                    if let Some((r, commits_vec)) = data.first() {
+                        (r.clone(), commits_vec.first().cloned().unwrap_or_default())
                     } else {
                         (PathBuf::new(), String::new())
                     }
-                };
-                let details = if detailed_commit_view {
-                    // Show the full multi-line commit block as the detail
-                    commit_line.clone()
-                } else {
-                    let hash = commit_line.split_whitespace().next().unwrap_or("");
-                    get_commit_details(&repo_path, hash).unwrap_or_else(|e| e.to_string())
-                };
-                // clear detail region
-                f.render_widget(Clear, detail_chunk);
-                // draw border around detail
-                let detail_block = Block::default()
-                    .title("Details")
-                    .borders(Borders::ALL)
-                    .style(Style::default().fg(bg_magenta));
-                f.render_widget(detail_block, detail_chunk);
-                // define padded inner area
-                let padded = Rect {
-                    x: detail_chunk.x + 1,
-                    y: detail_chunk.y + 1,
-                    width: detail_chunk.width.saturating_sub(2),
-                    height: detail_chunk.height.saturating_sub(2),
-                };
-                // clear inner region too
-                f.render_widget(Clear, padded);
-                // fill padded area with spaces to erase any leftover text
-                let blank_lines = vec![" ".repeat(padded.width as usize); padded.height as usize].join("\n");
-                let blank_para = Paragraph::new(blank_lines.clone());
-                f.render_widget(blank_para, padded);
-                // split into text + scrollbar
-                let detail_chunks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
-                    .split(padded);
-                // render detail text
-                let para = Paragraph::new(details.clone())
-                    .wrap(Wrap { trim: false })
-                    .scroll((detail_scroll, 0))
-                    .style(Style::default().fg(bg_fg));
-                f.render_widget(para, detail_chunks[0]);
-                // render scrollbar
-                let lines = details.lines().count();
-                let mut ds = ScrollbarState::default()
-                    .position(detail_scroll as usize)
-                    .content_length(lines);
-                f.render_stateful_widget(
-                    Scrollbar::default().orientation(ScrollbarOrientation::VerticalRight),
-                    detail_chunks[1],
-                    &mut ds,
-                );
+                })
+            } else if let Some((r, commits_vec)) = data.get(selected_repo_index) {
+                (r.clone(), commits_vec.get(sel_idx).cloned().unwrap_or_default())
+            } else {
+                (PathBuf::new(), String::new())
             }
-        }
+        };
+        let details = if detailed_commit_view {
+            // Show the full multi-line commit block as the detail
+            commit_line.clone()
+        } else {
+            let hash = commit_line.split(|c: char| c == '|' || c.is_whitespace()).next().unwrap_or("");
+            get_commit_details(&repo_path, hash).unwrap_or_else(|e| e.to_string())
+        };
+        let ticket_lines: Vec<String> = extract_tickets(&details).iter()
+            .filter_map(|key| tickets.get(key))
+            .map(|info| format!("{}  {} [{}]", info.key, info.title, info.status))
+            .collect();
+        let details = if ticket_lines.is_empty() {
+            details
+        } else {
+            format!("Tickets:\n{}\n\n{}", ticket_lines.join("\n"), details)
+        };
+        // clear detail region
+        f.render_widget(Clear, detail_chunk);
+        // draw border around detail
+        let detail_block = Block::default()
+            .title("Details")
+            .borders(Borders::ALL)
+            .style(Style::default().fg(bg_magenta));
+        f.render_widget(detail_block, detail_chunk);
+        // define padded inner area
+        let padded = Rect {
+            x: detail_chunk.x + 1,
+            y: detail_chunk.y + 1,
+            width: detail_chunk.width.saturating_sub(2),
+            height: detail_chunk.height.saturating_sub(2),
+        };
+        // clear inner region too
+        f.render_widget(Clear, padded);
+        // fill padded area with spaces to erase any leftover text
+        let blank_lines = vec![" ".repeat(padded.width as usize); padded.height as usize].join("\n");
+        let blank_para = Paragraph::new(blank_lines.clone());
+        f.render_widget(blank_para, padded);
+        // split into text + scrollbar
+        let detail_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(padded);
+        // render detail text
+        let para = Paragraph::new(details.clone())
+            .wrap(Wrap { trim: false })
+            .scroll((detail_scroll, 0))
+            .style(Style::default().fg(bg_fg));
+        f.render_widget(para, detail_chunks[0]);
+        // render scrollbar
+        let lines = details.lines().count();
+        let mut ds = ScrollbarState::default()
+            .position(detail_scroll as usize)
+            .content_length(lines);
+        f.render_stateful_widget(
+            Scrollbar::default().orientation(ScrollbarOrientation::VerticalRight),
+            detail_chunks[1],
+            &mut ds,
+        );
     } 
 
     // footer
-    let filter_label = if filter_by_user {"u: Only mine"} else {"u: All"};
+    let filter_label = if filter_by_user {
+        "u: Only mine".to_string()
+    } else if !author_picker.selected.is_empty() {
+        format!("u: {} picked", author_picker.selected.len())
+    } else {
+        "u: All".to_string()
+    };
     let detail_label = if detailed_commit_view {"d: Details ON"} else {"d: Details OFF"};
-    let footer = Paragraph::new(format!(
-        "Tab/Shift+Tab Timeframe | ↑/↓/ or h/j/k/l Navigation | <Space> Details |  m Mark | s Show Marked | a AI summary | {} | {} | Q Quit",
-        filter_label, detail_label
-    ))
-    .block(Block::default().borders(Borders::ALL))
-    .style(if dim_bg { theme.footer.fg(theme.blurred_border) } else { theme.footer });
-    f.render_widget(footer, vertical_chunks[1]);
+    if search.active || search.is_filtering() {
+        render_search_bar(f, theme, search, data, vertical_chunks[1]);
+    } else {
+        // Messages from actions like y (copy permalink) replace the hints until the next key
+        let status = popup_quote
+            .map(|arc| arc.lock().unwrap())
+            .filter(|p| !p.visible)
+            .and_then(|p| p.status.clone());
+        let footer = Paragraph::new(status.unwrap_or_else(|| format!(
+            "Tab/Shift+Tab Timeframe | r Date range | ↑/↓/ or h/j/k/l Navigation | <Space> Details |  m Mark | s Show Marked | / Search | p Authors | a AI summary | R Regenerate | T Team report | e Export | J Journal | b Notes | H History | o Open commit | y Copy link | {} | {} | Q Quit",
+            filter_label, detail_label
+        )))
+        .block(Block::default().borders(Borders::ALL))
+        .style(if dim_bg { theme.footer.fg(theme.blurred_border) } else { theme.footer });
+        f.render_widget(footer, vertical_chunks[1]);
+    }
 
     // popup
     if let Some(arc) = popup_quote {
-        let popup = arc.lock().unwrap();
+        let mut popup = arc.lock().unwrap();
         if popup.visible {
             // Dim the background
             let area = f.area();
@@ -546,7 +607,20 @@ pub fn render_commits(
             } else {
                 "Project".to_string()
             };
-            let title = format!("\u{1F916}  AI Summary for {}", project);
+            let badge = if popup.loading {
+                ""
+            } else if popup.offline {
+                " (offline)"
+            } else if popup.cached {
+                " (cached)"
+            } else {
+                ""
+            };
+            let title = match &popup.preview {
+                Some(preview) => format!("\u{1F4DD}  Prompt for {}  ≈{} tokens", project, preview.tokens()),
+                None if popup.offline => format!("\u{1F4CB}  Summary for {}{}", project, badge),
+                None => format!("\u{1F916}  AI Summary for {}{}", project, badge),
+            };
             let interval = format!("Interval: {}", display_interval);
             let x_button = Span::styled("[X]", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
             let mut title_line = vec![
@@ -569,7 +643,6 @@ pub fn render_commits(
                 .title(Line::from(title_line));
 
             let scroll = popup.scroll;
-            let text_line_count = popup.text.lines().count() as u16;
 
             // Loading spinner/animation
             let spinner = if popup.loading {
@@ -580,10 +653,67 @@ pub fn render_commits(
                 String::new()
             };
 
-            // Content: always show popup.text (which includes variables if loading)
-            let padded_text = if popup.loading {
-                // Show spinner above the text
-                format!(
+            let mut text_line_count = popup.text.lines().count() as u16;
+            let mut scroll_position = popup.scroll as usize;
+            if let Some(preview) = popup.preview.as_mut() {
+                // The prompt as it will be sent: unwrapped, with the cursor line marked
+                let mut inner = block.inner(popup_area).inner(Margin { horizontal: 2, vertical: 1 });
+                // What redaction masked sits above the prompt and is not part of it
+                let masked_area = preview.masked.as_ref().map(|masked| {
+                    let area = Rect { height: 1, ..inner };
+                    inner.y += 2;
+                    inner.height = inner.height.saturating_sub(2);
+                    (area, masked.clone())
+                });
+                let editor = &mut preview.editor;
+                editor.scroll_to_cursor(inner.width as usize, inner.height as usize);
+                if !preview.editing {
+                    editor.hscroll = 0;
+                }
+                let lines: Vec<Line> = editor.lines.iter()
+                    .enumerate()
+                    .skip(editor.scroll)
+                    .take(inner.height as usize)
+                    .map(|(row, line)| {
+                        let line = Line::from(line.chars().skip(editor.hscroll).collect::<String>());
+                        if !preview.editing && row == editor.row {
+                            line.style(Style::default().bg(theme.selection_bg))
+                        } else {
+                            line
+                        }
+                    })
+                    .collect();
+                text_line_count = editor.lines.len() as u16;
+                scroll_position = editor.scroll;
+                f.render_widget(block, popup_area);
+                if let Some((area, masked)) = masked_area {
+                    f.render_widget(Paragraph::new(format!("Masked before sending: {}", masked)).style(Style::default().fg(theme.text_secondary).add_modifier(Modifier::ITALIC)), area);
+                }
+                f.render_widget(Paragraph::new(lines).style(theme.popup_text), inner);
+                if preview.editing {
+                    f.set_cursor_position(Position::new(
+                        inner.x + (editor.col - editor.hscroll) as u16,
+                        inner.y + (editor.row - editor.scroll) as u16,
+                    ));
+                }
+            } else if let Some(editor) = popup.editor.as_mut() {
+                // Edit mode: no wrapping, the view follows the cursor
+                let inner = block.inner(popup_area).inner(Margin { horizontal: 2, vertical: 1 });
+                editor.scroll_to_cursor(inner.width as usize, inner.height as usize);
+                let lines: Vec<Line> = editor.lines.iter()
+                    .skip(editor.scroll)
+                    .take(inner.height as usize)
+                    .map(|line| Line::from(line.chars().skip(editor.hscroll).collect::<String>()))
+                    .collect();
+                f.render_widget(block, popup_area);
+                f.render_widget(Paragraph::new(lines).style(theme.popup_text), inner);
+                f.set_cursor_position(Position::new(
+                    inner.x + (editor.col - editor.hscroll) as u16,
+                    inner.y + (editor.row - editor.scroll) as u16,
+                ));
+            } else if popup.loading && popup.chat.is_empty() {
+                // While loading, show the prompt variables as plain text below the spinner
+                let padded_text = format!(
                     "\n   {}Loading...\n\n{}\n",
                     spinner,
                     popup.text
@@ -591,25 +721,82 @@ pub fn render_commits(
                         .map(|line| format!("  {}  ", line))
                         .collect::<Vec<_>>()
                         .join("\n")
-                )
+                );
+                let para = Paragraph::new(padded_text)
+                    .block(block)
+                    .wrap(Wrap { trim: true })
+                    .alignment(Alignment::Left)
+                    .scroll((scroll, 0))
+                    .style(theme.popup_text);
+                f.render_widget(para, popup_area);
+            } else if popup.shown_sections().is_some() {
+                // A structured summary: foldable sections instead of its Markdown
+                let inner = block.inner(popup_area).inner(Margin { horizontal: 2, vertical: 1 });
+                let p = &mut *popup;
+                let sections = p.sections.as_mut().unwrap();
+                let (lines, cursor_line) = section_lines(sections, inner.width, theme);
+                if sections.follow_cursor {
+                    let cursor_line = cursor_line as u16;
+                    if cursor_line < p.scroll {
+                        p.scroll = cursor_line;
+                    } else if cursor_line >= p.scroll + inner.height {
+                        p.scroll = cursor_line + 1 - inner.height;
+                    }
+                    sections.follow_cursor = false;
+                }
+                // Folding can leave the view below the end
+                p.scroll = p.scroll.min((lines.len() as u16).saturating_sub(1));
+                text_line_count = lines.len() as u16;
+                scroll_position = p.scroll as usize;
+                f.render_widget(block, popup_area);
+                f.render_widget(Paragraph::new(lines).scroll((p.scroll, 0)).style(theme.popup_text), inner);
             } else {
-                format!(
-                    "\n{}\n",
-                    popup.text
-                        .lines()
-                        .map(|line| format!("  {}  ", line))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                )
-            };
-
-            let para = Paragraph::new(padded_text)
-                .block(block)
-                .wrap(Wrap { trim: true })
-                .alignment(Alignment::Left)
-                .scroll((scroll, 0))
-                .style(theme.popup_text);
-            f.render_widget(para, popup_area);
+                // The summary is Markdown; render it, while copy/export keep the source
+                let mut inner = block.inner(popup_area).inner(Margin { horizontal: 2, vertical: 1 });
+                let question_style = Style::default().fg(theme.text_highlight).add_modifier(Modifier::BOLD);
+                // Earlier exchanges first, the latest answer last
+                let mut lines: Vec<Line> = vec![];
+                for turn in &popup.chat {
+                    if let Some(question) = &turn.question {
+                        lines.push(Line::from(Span::styled(format!("› {}", question), question_style)));
+                        lines.push(Line::default());
+                    }
+                    lines.extend(render_markdown(&turn.answer, inner.width, theme));
+                    lines.push(Line::default());
+                    lines.push(Line::from(Span::styled("─".repeat(inner.width as usize), Style::default().fg(theme.blurred_border))));
+                    lines.push(Line::default());
+                }
+                let latest = lines.len() as u16;
+                if let Some(question) = &popup.question {
+                    lines.push(Line::from(Span::styled(format!("› {}", question), question_style)));
+                    lines.push(Line::default());
+                }
+                if popup.loading {
+                    lines.push(Line::from(format!("{}Thinking...", spinner)));
+                } else {
+                    lines.extend(render_markdown(&popup.text, inner.width, theme));
+                }
+                text_line_count = lines.len() as u16;
+                if popup.scroll_to_latest {
+                    popup.scroll = latest;
+                    popup.scroll_to_latest = false;
+                }
+                f.render_widget(block, popup_area);
+                if let Some(input) = &popup.chat_input {
+                    // Input line at the bottom of the popup
+                    let input_area = Rect { y: inner.y + inner.height.saturating_sub(1), height: 1, ..inner };
+                    inner.height = inner.height.saturating_sub(2);
+                    let prompt = format!("› {}", input);
+                    // Keep the end of a long question visible
+                    let skip = prompt.chars().count().saturating_sub(input_area.width.saturating_sub(1) as usize);
+                    let visible: String = prompt.chars().skip(skip).collect();
+                    let cursor_x = input_area.x + visible.chars().count() as u16;
+                    f.render_widget(Paragraph::new(visible).style(question_style), input_area);
+                    f.set_cursor_position(Position::new(cursor_x, input_area.y));
+                }
+                f.render_widget(Paragraph::new(lines).scroll((popup.scroll, 0)).style(theme.popup_text), inner);
+                scroll_position = popup.scroll as usize;
+            }
 
             // Draw a vertical scrollbar inside the popup
             let scrollbar_area = Rect {
@@ -619,7 +806,7 @@ pub fn render_commits(
                 height: popup_area.height.saturating_sub(2),
             };
             let mut sb = ScrollbarState::default()
-                .position(scroll as usize)
+                .position(scroll_position)
                 .content_length(text_line_count as usize);
             f.render_stateful_widget(Scrollbar::default().orientation(ScrollbarOrientation::VerticalRight), scrollbar_area, &mut sb);
 
@@ -630,7 +817,21 @@ pub fn render_commits(
                 width: popup_area.width,
                 height: 1,
             };
-            let footer = Paragraph::new("Press c to copy | ↑/↓ scroll | Esc close")
+            let footer_text = if popup.preview.as_ref().is_some_and(|p| p.editing) {
+                "Editing prompt | arrows/Home/End move | Enter new line | Esc done".to_string()
+            } else if popup.preview.is_some() {
+                "Review the prompt | Enter send | ↑/↓ line | d delete line | i edit | E $EDITOR | Esc cancel".to_string()
+            } else if popup.editor.is_some() {
+                "Editing | arrows/Home/End move | Enter new line | Esc done".to_string()
+            } else if popup.chat_input.is_some() {
+                "Follow-up | Enter send | Esc cancel".to_string()
+            } else if popup.status.is_none() && popup.shown_sections().is_some() {
+                "↑/↓ section | Space fold | z fold all | c copy | Enter follow-up | i edit | e export | J journal | P publish | Esc close".to_string()
+            } else {
+                popup.status.clone()
+                    .unwrap_or_else(|| "Press c to copy | Enter follow-up | i edit | E $EDITOR | e export | J journal | P publish | ↑/↓ scroll | Esc close".to_string())
+            };
+            let footer = Paragraph::new(footer_text)
                 .style(Style::default().fg(theme.text_secondary).add_modifier(Modifier::ITALIC));
             f.render_widget(footer, footer_area);
         }
@@ -672,6 +873,430 @@ pub fn render_commits(
             f.render_widget(para, popup_area);
         }
     }
+
+    if range_dialog.visible {
+        render_range_dialog(f, theme, range_dialog);
+    }
+
+    if author_picker.visible {
+        render_author_picker(f, theme, author_picker);
+    }
+
+    if export_dialog.visible {
+        render_export_dialog(f, theme, export_dialog, time_range);
+    }
+
+    if publish_dialog.visible {
+        render_publish_dialog(f, theme, publish_dialog, publish_targets);
+    }
+
+    if preset_picker.visible {
+        render_preset_picker(f, theme, preset_picker, presets);
+    }
+
+    if notes_editor.visible {
+        render_notes_editor(f, theme, notes_editor);
+    }
+
+    if history.visible {
+        render_history(f, theme, history);
+    }
+}
+
+/// Renders the summary archive: a list with a preview, or a diff of two summaries.
+/// A structured summary with ▸/▾ fold markers on its section headers, and the
+/// line of the header under the cursor. Folded sections show how much they hide.
+fn section_lines(sections: &SummarySections, width: u16, theme: &Theme) -> (Vec<Line<'static>>, usize) {
+    let cursor = sections.visible().get(sections.cursor).copied();
+    let mut lines: Vec<Line<'static>> = vec![];
+    let mut cursor_line = 0;
+    let mut header = |lines: &mut Vec<Line<'static>>, id: SectionId, indent: usize, title: String, hidden: usize, style: Style| {
+        let folded = sections.folded.contains(&id);
+        let mut spans = vec![
+            Span::raw(" ".repeat(indent)),
+            Span::styled(format!("{} {}", if folded { "▸" } else { "▾" }, title), style),
+        ];
+        if folded {
+            spans.push(Span::styled(format!("  ({})", hidden), Style::default().fg(theme.text_secondary)));
+        }
+        let mut line = Line::from(spans);
+        if cursor == Some(id) {
+            cursor_line = lines.len();
+            line = line.style(Style::default().bg(theme.selection_bg));
+        }
+        lines.push(line);
+        !folded
+    };
+    // Bullets are Markdown, indented below their header
+    let body = |lines: &mut Vec<Line<'static>>, markdown: String| {
+        for line in render_markdown(&markdown, width.saturating_sub(6), theme) {
+            let mut spans = vec![Span::raw("      ")];
+            spans.extend(line.spans);
+            lines.push(Line::from(spans));
+        }
+    };
+    let topic_style = Style::default().fg(theme.text_highlight).add_modifier(Modifier::BOLD);
+    for (i, project) in sections.summary.projects.iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::default());
+        }
+        let hidden = project.topics.len() + usize::from(!project.days.is_empty());
+        if !header(&mut lines, SectionId::Project(i), 0, project.name.trim().to_string(), hidden, theme.popup_title) {
+            continue;
+        }
+        for (j, topic) in project.topics.iter().enumerate() {
+            if header(&mut lines, SectionId::Topic(i, j), 2, topic.title.trim().to_string(), topic.bullets.len(), topic_style) {
+                body(&mut lines, topic.bullets.iter().map(|b| format!("- {}", b.trim())).collect::<Vec<_>>().join("\n"));
+            }
+        }
+        if !project.days.is_empty() && header(&mut lines, SectionId::Days(i), 2, "Daily breakdown".to_string(), project.days.len(), topic_style) {
+            body(&mut lines, project.days.iter().map(|d| format!("- **{}**: {}", d.date.trim(), d.text.trim())).collect::<Vec<_>>().join("\n"));
+        }
+    }
+    (lines, cursor_line)
+}
+
+fn render_history(f: &mut Frame, theme: &Theme, history: &HistoryView) {
+    let popup_area = centered_rect(70, 80, f.area());
+    f.render_widget(Clear, popup_area);
+    if let Some(diff) = &history.diff {
+        let lines: Vec<Line> = diff.iter().map(|(tag, line)| match tag {
+            ChangeTag::Insert => Line::from(Span::styled(format!("+ {}", line), Style::default().fg(Color::Green))),
+            ChangeTag::Delete => Line::from(Span::styled(format!("- {}", line), Style::default().fg(Color::Red))),
+            ChangeTag::Equal => Line::from(Span::styled(format!("  {}", line), Style::default().fg(theme.text_secondary))),
+        }).collect();
+        let title = history.message.clone().unwrap_or_default();
+        let para = Paragraph::new(lines)
+            .block(Block::default()
+                .title(Span::styled(format!("\u{1F4DC}  Diff {}", title), theme.popup_title))
+                .title_bottom(Line::from(" ↑/↓ scroll | Esc back ").centered())
+                .borders(Borders::ALL)
+                .style(theme.popup_border))
+            .wrap(Wrap { trim: false })
+            .scroll((history.diff_scroll, 0));
+        f.render_widget(para, popup_area);
+        return;
+    }
+    let hints = if history.searching {
+        " Type to search | Enter done | Esc clear ".to_string()
+    } else {
+        history.message.clone().map(|m| format!(" {} ", m))
+            .unwrap_or_else(|| " Enter reopen | c copy | d mark/diff | / search | Esc close ".to_string())
+    };
+    let block = Block::default()
+        .title(Span::styled("\u{1F4DC}  Summary history", theme.popup_title))
+        .title_bottom(Line::from(hints).centered())
+        .borders(Borders::ALL)
+        .style(theme.popup_border);
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Percentage(40), Constraint::Min(3)])
+        .split(inner);
+
+    let search = if history.searching || !history.query.is_empty() {
+        Line::from(vec![
+            Span::styled("/ ", Style::default().fg(theme.text_highlight)),
+            Span::styled(history.query.clone(), Style::default().fg(theme.text)),
+            Span::styled(if history.searching { "▏" } else { "" }, Style::default().fg(theme.text_highlight)),
+        ])
+    } else {
+        Line::from(Span::styled("Press / to search summaries", Style::default().fg(theme.text_secondary)))
+    };
+    f.render_widget(Paragraph::new(search), chunks[0]);
+
+    let entries = history.filtered();
+    let items: Vec<ListItem> = if entries.is_empty() {
+        vec![ListItem::new(Span::styled("No archived summaries.", Style::default().fg(theme.text_secondary)))]
+    } else {
+        entries.iter().map(|entry| {
+            let mark = if history.marked.as_deref() == Some(entry.id.as_str()) { "◆ " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::styled(mark, Style::default().fg(theme.text_highlight)),
+                Span::styled(entry.label(), Style::default().fg(theme.text)),
+            ]))
+        }).collect()
+    };
+    let mut state = ListState::default();
+    state.select(Some(history.cursor));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::BOTTOM).border_style(Style::default().fg(theme.blurred_border)))
+        .highlight_style(Style::default().bg(theme.selection_bg).add_modifier(Modifier::BOLD))
+        .highlight_symbol("▶ ");
+    f.render_stateful_widget(list, chunks[1], &mut state);
+
+    if let Some(entry) = entries.get(history.cursor) {
+        let preview_area = chunks[2].inner(Margin { horizontal: 1, vertical: 0 });
+        f.render_widget(Paragraph::new(render_markdown(&entry.text, preview_area.width, theme)), preview_area);
+    }
+}
+
+/// Renders the plans/blockers editor; the active field shows a cursor.
+fn render_notes_editor(f: &mut Frame, theme: &Theme, editor: &NotesEditor) {
+    let popup_area = centered_rect(60, 60, f.area());
+    f.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .title(Span::styled(format!("\u{1F4DD}  Standup notes for {}", editor.date), theme.popup_title))
+        .title_bottom(Line::from(" Tab switch field | Enter new line | Esc save & close ").centered())
+        .borders(Borders::ALL)
+        .style(theme.popup_border);
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    let fields = [
+        (NotesField::Plans, "Today I plan", &editor.notes.plans, chunks[0]),
+        (NotesField::Blockers, "Blockers", &editor.notes.blockers, chunks[1]),
+    ];
+    for (field, title, text, area) in fields {
+        let active = editor.field == field;
+        let text = if active { format!("{}▏", text) } else { text.to_string() };
+        let border = if active { theme.focus_border } else { theme.blurred_border };
+        let para = Paragraph::new(text)
+            .block(Block::default().title(title).borders(Borders::ALL).border_style(Style::default().fg(border)))
+            .wrap(Wrap { trim: false })
+            .style(theme.popup_text);
+        f.render_widget(para, area);
+    }
+}
+
+/// Renders the sidebar section with pull request and review activity.
+fn render_forge_activity(f: &mut Frame, theme: &Theme, activity: &ForgeActivity, area: Rect, border: Color) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("+{} ", activity.count(ActivityKind::Opened)), theme.repo_commit_count),
+        Span::styled(format!("✓{} ", activity.count(ActivityKind::Merged)), Style::default().fg(Color::Green)),
+        Span::styled(format!("👀{} ", activity.count(ActivityKind::Reviewed)), theme.commit_author),
+        Span::styled(format!("💬{}", activity.count(ActivityKind::Commented)), Style::default().fg(theme.text_secondary)),
+    ])];
+    if activity.loading {
+        lines.push(Line::from(Span::styled("Loading...", Style::default().fg(theme.text_secondary).add_modifier(Modifier::ITALIC))));
+    } else if activity.items.is_empty() && activity.errors.is_empty() {
+        lines.push(Line::from(Span::styled("No activity in this range", Style::default().fg(theme.text_secondary))));
+    }
+    for item in activity.items.iter().rev() {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", item.reference), theme.commit_hash),
+            Span::styled(item.title.clone(), Style::default().fg(theme.text)),
+        ]));
+    }
+    for error in &activity.errors {
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+    let block = Block::default().title("Reviews & PRs").borders(Borders::ALL).style(Style::default().fg(border));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Renders the export path prompt with the file the path resolves to.
+fn render_export_dialog(f: &mut Frame, theme: &Theme, dialog: &ExportDialog, time_range: &TimeRange) {
+    let popup_area = centered_rect(60, 20, f.area());
+    f.render_widget(Clear, popup_area);
+    let (target, _) = export_target(&dialog.path, None, time_range);
+    let (note, hints) = if dialog.confirm_overwrite {
+        (
+            Span::styled(format!("{} already exists", target.display()), Style::default().fg(Color::Yellow)),
+            " Enter overwrite | Esc change path ",
+        )
+    } else {
+        (Span::styled(format!("→ {}", target.display()), Style::default().fg(theme.text_secondary)), " Enter export | Esc cancel ")
+    };
+    let lines = vec![
+        Line::from(Span::styled(format!("{}▏", dialog.path), Style::default().fg(theme.text))),
+        Line::from(""),
+        Line::from(note),
+    ];
+    let para = Paragraph::new(lines)
+        .block(Block::default()
+            .title(Span::styled("\u{1F4BE}  Export to...", theme.popup_title))
+            .title_bottom(Line::from(hints).centered())
+            .borders(Borders::ALL)
+            .style(theme.popup_border))
+        .wrap(Wrap { trim: false })
+        .style(theme.popup_text);
+    f.render_widget(para, popup_area);
+}
+
+/// Renders the confirmation dialog listing the webhook targets.
+fn render_publish_dialog(f: &mut Frame, theme: &Theme, dialog: &PublishDialog, targets: &[PublishTarget]) {
+    let popup_area = centered_rect(40, 30, f.area());
+    f.render_widget(Clear, popup_area);
+    let items: Vec<ListItem> = targets.iter().map(|target| {
+        ListItem::new(Line::from(vec![
+            Span::styled(target.name.clone(), Style::default().fg(theme.text)),
+            Span::styled(format!("  ({})", target.kind), Style::default().fg(theme.text_secondary)),
+        ]))
+    }).collect();
+    let mut state = ListState::default();
+    state.select(Some(dialog.cursor));
+    let list = List::new(items)
+        .block(Block::default()
+            .title(Span::styled("\u{1F4E3}  Publish summary to...", theme.popup_title))
+            .title_bottom(Line::from(" Enter/y send | Esc cancel ").centered())
+            .borders(Borders::ALL)
+            .style(theme.popup_border))
+        .highlight_style(Style::default().bg(theme.selection_bg).add_modifier(Modifier::BOLD))
+        .highlight_symbol("▶ ")
+        .style(theme.popup_text);
+    f.render_stateful_widget(list, popup_area, &mut state);
+}
+
+/// Renders the prompt preset picker with each preset's description.
+fn render_preset_picker(f: &mut Frame, theme: &Theme, picker: &PresetPicker, presets: &[Preset]) {
+    let popup_area = centered_rect(50, 40, f.area());
+    f.render_widget(Clear, popup_area);
+    let items: Vec<ListItem> = presets.iter().map(|preset| {
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("{:<15}", preset.name), Style::default().fg(theme.text)),
+            Span::styled(format!(" {}", preset.description), Style::default().fg(theme.text_secondary)),
+        ]))
+    }).collect();
+    let mut state = ListState::default();
+    state.select(Some(picker.cursor));
+    let list = List::new(items)
+        .block(Block::default()
+            .title(Span::styled("\u{1F4DD}  Summarize as...", theme.popup_title))
+            .title_bottom(Line::from(" Enter summarize | Esc cancel ").centered())
+            .borders(Borders::ALL)
+            .style(theme.popup_border))
+        .highlight_style(Style::default().bg(theme.selection_bg).add_modifier(Modifier::BOLD))
+        .highlight_symbol("▶ ")
+        .style(theme.popup_text);
+    f.render_stateful_widget(list, popup_area, &mut state);
+}
+
+/// Renders the author picker with commit counts and checkboxes.
+fn render_author_picker(f: &mut Frame, theme: &Theme, picker: &AuthorPicker) {
+    let popup_area = centered_rect(50, 60, f.area());
+    f.render_widget(Clear, popup_area);
+    let items: Vec<ListItem> = if picker.authors.is_empty() {
+        vec![ListItem::new(Span::styled("No authors with commits in this range.", Style::default().fg(theme.text_secondary)))]
+    } else {
+        picker.authors.iter().map(|author| {
+            let checked = if picker.pending.contains(&author.email) { "[x]" } else { "[ ]" };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", checked), Style::default().fg(theme.text_highlight)),
+                Span::styled(author.name.clone(), theme.commit_author),
+                Span::styled(format!(" <{}>", author.email), Style::default().fg(theme.text_secondary)),
+                Span::styled(format!("  {} commit{}", author.commits, if author.commits == 1 { "" } else { "s" }), theme.repo_commit_count),
+            ]))
+        }).collect()
+    };
+    let mut state = ListState::default();
+    state.select(Some(picker.cursor));
+    let list = List::new(items)
+        .block(Block::default()
+            .title(Span::styled("\u{1F465}  Authors", theme.popup_title))
+            .title_bottom(Line::from(" Space toggle | c clear | Enter apply | Esc cancel ").centered())
+            .borders(Borders::ALL)
+            .style(theme.popup_border))
+        .highlight_style(Style::default().bg(theme.selection_bg).add_modifier(Modifier::BOLD))
+        .highlight_symbol("▶ ")
+        .style(theme.popup_text);
+    f.render_stateful_widget(list, popup_area, &mut state);
+}
+
+/// Renders the `/` search bar in place of the footer.
+fn render_search_bar(f: &mut Frame, theme: &Theme, search: &SearchState, data: &CommitData, area: Rect) {
+    let hits: usize = data.iter().map(|(_, c)| c.len()).sum();
+    let cursor = if search.active { "█" } else { "" };
+    let mut spans = vec![
+        Span::styled("/", theme.popup_title),
+        Span::styled(format!("{}{}", search.query, cursor), Style::default().fg(theme.text)),
+        Span::raw("  "),
+        Span::styled(format!("{} match{}", hits, if hits == 1 { "" } else { "es" }), Style::default().fg(theme.text_highlight)),
+    ];
+    if search.invalid_regex {
+        spans.push(Span::styled("  (invalid regex, matching literally)", Style::default().fg(Color::Red)));
+    }
+    let ai_scope = if search.filter_summary { "f: AI uses filtered" } else { "f: AI uses all" };
+    let keys = if search.active { "Enter keep | Esc clear" } else { "n/N next/prev | / edit | Esc clear" };
+    spans.push(Span::styled(format!("  | {} | {}", keys, ai_scope), theme.footer));
+    let bar = Paragraph::new(Line::from(spans))
+        .block(Block::default().borders(Borders::ALL).style(Style::default().fg(theme.focus_border)));
+    f.render_widget(bar, area);
+}
+
+/// Renders the date range dialog with a month calendar.
+fn render_range_dialog(f: &mut Frame, theme: &Theme, dialog: &DateRangeDialog) {
+    let area = f.area();
+    let width = 44.min(area.width);
+    let height = 16.min(area.height);
+    let popup_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .title(Span::styled("\u{1F4C5}  Date range", theme.popup_title))
+        .borders(Borders::ALL)
+        .style(theme.popup_border);
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Length(9), Constraint::Min(1)])
+        .split(inner);
+
+    let (from_label, to_label) = match dialog.from {
+        Some(from) => (from.format("%Y-%m-%d").to_string(), format!("{} ?", dialog.cursor.format("%Y-%m-%d"))),
+        None => (format!("{} ?", dialog.cursor.format("%Y-%m-%d")), "-".to_string()),
+    };
+    let status = Paragraph::new(Line::from(vec![
+        Span::styled(" From: ", Style::default().fg(theme.text_secondary)),
+        Span::styled(from_label, Style::default().fg(theme.text_highlight)),
+        Span::styled("  To: ", Style::default().fg(theme.text_secondary)),
+        Span::styled(to_label, Style::default().fg(theme.text_highlight)),
+    ]));
+    f.render_widget(status, chunks[0]);
+
+    // Highlight the picked span and the cursor day
+    let picked = dialog.from.map(|from| if from <= dialog.cursor { (from, dialog.cursor) } else { (dialog.cursor, from) });
+    let day_style = |day: chrono::NaiveDate| {
+        if day == dialog.cursor {
+            Style::default().fg(theme.selection_fg).add_modifier(Modifier::REVERSED | Modifier::BOLD)
+        } else if picked.is_some_and(|(start, end)| start <= day && day <= end) {
+            Style::default().bg(theme.selection_bg).fg(theme.selection_fg)
+        } else if day.month() != dialog.cursor.month() {
+            Style::default().fg(theme.blurred_border)
+        } else {
+            theme.popup_text
+        }
+    };
+    let mut lines = vec![
+        Line::styled(dialog.cursor.format("%B %Y").to_string(), theme.popup_title).centered(),
+        Line::styled(" Su Mo Tu We Th Fr Sa", Style::default().fg(theme.text_secondary)),
+    ];
+    lines.extend(month_weeks(dialog.cursor).into_iter().map(|week| {
+        Line::from(week.into_iter().map(|day| Span::styled(format!(" {:>2}", day.day()), day_style(day))).collect::<Vec<_>>())
+    }));
+    let calendar_area = Rect {
+        x: chunks[1].x + chunks[1].width.saturating_sub(22) / 2,
+        width: chunks[1].width.min(22),
+        ..chunks[1]
+    };
+    f.render_widget(Paragraph::new(lines), calendar_area);
+
+    let hint = if dialog.from.is_some() { "Enter pick end" } else { "Enter pick start" };
+    let footer = Paragraph::new(format!("←/→ day | ↑/↓ week | PgUp/PgDn month\n{} | Esc cancel", hint))
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(theme.text_secondary).add_modifier(Modifier::ITALIC));
+    f.render_widget(footer, chunks[2]);
+}
+
+/// The weeks (Sunday to Saturday) that overlap the month of `date`.
+fn month_weeks(date: chrono::NaiveDate) -> Vec<Vec<chrono::NaiveDate>> {
+    let first = date.with_day(1).unwrap_or(date);
+    let mut week_start = first - chrono::Days::new(first.weekday().num_days_from_sunday() as u64);
+    let mut weeks = vec![];
+    while week_start.month() == first.month() || week_start < first {
+        weeks.push(week_start.iter_days().take(7).collect());
+        week_start = week_start + chrono::Days::new(7);
+    }
+    weeks
 }
 
 /// Centers a rectangle within another rectangle.
@@ -2,11 +2,28 @@ use std::path::PathBuf;
 use anyhow::Result;
 use ratatui::prelude::Frame;
 use crate::git::get_commit_details;
+use crate::models::SearchState;
+use regex::Regex;
+use once_cell::sync::Lazy;
 
 // Type alias for commit data for clarity
 pub type CommitData = Vec<(PathBuf, Vec<String>)>;
 
-pub fn get_active_commits<'a>(commits: &'a CommitData, selected_repo_index: usize) -> Option<&'a Vec<String>> {
+// Compile the ticket regex once for all uses
+pub static TICKET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Z]+-\d+").unwrap());
+
+/// Ticket numbers (e.g. `CPT-123`) mentioned in `text`, in order and without duplicates.
+pub fn extract_tickets(text: &str) -> Vec<String> {
+    let mut tickets: Vec<String> = vec![];
+    for m in TICKET_REGEX.find_iter(text) {
+        if !tickets.iter().any(|t| t == m.as_str()) {
+            tickets.push(m.as_str().to_string());
+        }
+    }
+    tickets
+}
+
+pub fn get_active_commits(commits: &CommitData, selected_repo_index: usize) -> Option<&Vec<String>> {
     if selected_repo_index == usize::MAX {
         None
     } else {
@@ -17,6 +34,62 @@ pub fn get_active_commits<'a>(commits: &'a CommitData, selected_repo_index: usiz
     }
 }
 
+/// The hash at the start of a commit line (`hash|date|...`) or detailed block (`hash date ...`).
+pub fn commit_hash(commit: &str) -> &str {
+    commit.split(|c: char| c == '|' || c.is_whitespace()).next().unwrap_or("")
+}
+
+/// The repo and commit line at `commit_index` of the list shown for `selected_repo_index`.
+pub fn selected_commit(commits: &CommitData, selected_repo_index: usize, commit_index: usize) -> Option<(&PathBuf, &String)> {
+    if selected_repo_index == usize::MAX {
+        commits.iter()
+            .flat_map(|(repo, lines)| lines.iter().map(move |line| (repo, line)))
+            .nth(commit_index)
+    } else {
+        commits.get(selected_repo_index).and_then(|(repo, lines)| lines.get(commit_index).map(|line| (repo, line)))
+    }
+}
+
+/// Formats commits of all repos for a prompt, prefixing each line with its repo name.
+pub fn format_commits_for_prompt(commits: &CommitData) -> String {
+    commits.iter()
+        .flat_map(|(repo, msgs)| {
+            let repo_name = repo.file_name().unwrap_or_default().to_string_lossy();
+            msgs.iter().map(move |msg| format!("[{}] {}", repo_name, msg))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Checks a commit line against the search regex: hash, author and subject
+/// (which carries the tickets). Detailed multi-line commits are matched as a whole.
+pub fn commit_matches(commit: &str, re: &Regex, filter_by_user: bool) -> bool {
+    if commit.contains('\n') {
+        return re.is_match(commit);
+    }
+    let parts: Vec<&str> = if filter_by_user {
+        commit.splitn(3, '|').collect()
+    } else {
+        commit.splitn(4, '|').collect()
+    };
+    let fields: &[usize] = if filter_by_user { &[0, 2] } else { &[0, 2, 3] };
+    fields.iter().filter_map(|i| parts.get(*i)).any(|field| re.is_match(field.trim()))
+}
+
+/// Returns the commits matching the search query, dropping repos without hits.
+pub fn filter_commits(commits: &CommitData, search: &SearchState, filter_by_user: bool) -> CommitData {
+    let Some(re) = &search.regex else {
+        return commits.clone();
+    };
+    commits
+        .iter()
+        .filter_map(|(repo, repo_commits)| {
+            let hits: Vec<String> = repo_commits.iter().filter(|c| commit_matches(c, re, filter_by_user)).cloned().collect();
+            if hits.is_empty() { None } else { Some((repo.clone(), hits)) }
+        })
+        .collect()
+}
+
 #[allow(dead_code)]
 pub fn get_sidebar_height() -> Result<usize> {
     let (_cols, rows) = crossterm::terminal::size()?;
@@ -75,14 +148,12 @@ pub fn calculate_max_detail_scroll(
             idx += repo_commits.len();
         }
         return Ok(0);
-    } else if let Some((repo, repo_commits)) = commits.get(selected_repo_index) {
-        if let Some(commit) = repo_commits.get(commit_index) {
-            let commit_hash = commit.split_whitespace().next().unwrap_or("");
-            if !commit_hash.is_empty() {
-                match get_commit_details(repo, commit_hash) {
-                    Ok(details) => return calculate_max_scroll(details, 15),
-                    Err(_) => return Ok(0),
-                }
+    } else if let Some((repo, repo_commits)) = commits.get(selected_repo_index) && let Some(commit) = repo_commits.get(commit_index) {
+        let commit_hash = commit.split_whitespace().next().unwrap_or("");
+        if !commit_hash.is_empty() {
+            match get_commit_details(repo, commit_hash) {
+                Ok(details) => return calculate_max_scroll(details, 15),
+                Err(_) => return Ok(0),
             }
         }
     }